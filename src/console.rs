@@ -0,0 +1,215 @@
+use bevy::{
+    ecs::system::SystemId,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+    ui::UiSystem,
+};
+use std::collections::BTreeMap;
+
+use crate::{
+    keymap::{Action, Keymap},
+    map::MapName,
+    title::UiResources,
+    GameState,
+};
+
+const MAX_CONSOLE_LINES: usize = 12;
+
+// the arguments after the command name
+pub type ConsoleArgs = In<Vec<String>>;
+
+#[derive(Debug, Resource, Default)]
+pub struct Console {
+    open: bool,
+    line: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        let start = self.output.len().saturating_sub(MAX_CONSOLE_LINES);
+        self.output.drain(..start);
+    }
+}
+
+#[derive(Debug)]
+struct ConsoleCommand {
+    help: &'static str,
+    system: SystemId<Vec<String>>,
+}
+
+#[derive(Debug, Resource, Default)]
+struct ConsoleCommands(BTreeMap<&'static str, ConsoleCommand>);
+
+#[derive(Debug, Component)]
+struct ConsoleText;
+
+// lets each plugin bring its own commands, run as systems taking the arguments
+pub trait ConsoleApp {
+    fn console_command<M>(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        system: impl IntoSystem<Vec<String>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl ConsoleApp for App {
+    fn console_command<M>(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        system: impl IntoSystem<Vec<String>, (), M> + 'static,
+    ) -> &mut Self {
+        let system = self.world_mut().register_system(system);
+        self.world_mut()
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .0
+            .insert(name, ConsoleCommand { help, system });
+        self
+    }
+}
+
+pub fn console_plugin(app: &mut App) {
+    app.init_resource::<Console>()
+        .add_systems(Startup, setup_console_ui)
+        .add_systems(
+            PreUpdate,
+            console_input.after(InputSystem).before(UiSystem::Focus),
+        )
+        .add_systems(Update, update_console_ui)
+        .console_command("help", "lists every command", help_command)
+        .console_command("state", "prints the game state and map", state_command);
+}
+
+fn setup_console_ui(mut commands: Commands, ui_resources: Res<UiResources>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                top: Val::Px(0.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.85).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(30),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+// while open the console eats the keyboard so nothing else reacts to the typing
+fn console_input(
+    mut commands: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut typed: EventReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+    registry: Res<ConsoleCommands>,
+    keymap: Res<Keymap>,
+) {
+    if keymap.just_pressed(Action::Console, &keys) {
+        console.open = !console.open;
+        typed.clear();
+        keys.reset_all();
+        return;
+    }
+
+    if !console.open {
+        typed.clear();
+        return;
+    }
+
+    for event in typed.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(text) => console.line.push_str(text),
+            Key::Space => console.line.push(' '),
+            Key::Backspace => _ = console.line.pop(),
+            Key::Escape => console.open = false,
+            Key::Enter => {
+                let line = std::mem::take(&mut console.line);
+                console.print(format!("> {line}"));
+
+                let mut words = line.split_whitespace().map(str::to_string);
+                let Some(name) = words.next() else {
+                    continue;
+                };
+
+                match registry.0.get(name.as_str()) {
+                    Some(command) => {
+                        commands.run_system_with_input(command.system, words.collect());
+                    }
+                    None => console.print(format!("unknown command {name}, try help")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    keys.reset_all();
+}
+
+fn update_console_ui(
+    console: Res<Console>,
+    mut root: Query<&mut Visibility, With<Node>>,
+    mut text: Query<(&mut Text, &Parent), With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let Ok((mut text, parent)) = text.get_single_mut() else {
+        return;
+    };
+
+    if let Ok(mut visibility) = root.get_mut(parent.get()) {
+        *visibility = if console.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    text.sections[0].value = console
+        .output
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .chain([format!("> {}_", console.line)])
+        .collect();
+}
+
+fn help_command(_: ConsoleArgs, registry: Res<ConsoleCommands>, mut console: ResMut<Console>) {
+    for (name, command) in registry.0.iter() {
+        console.print(format!("{name} - {}", command.help));
+    }
+}
+
+fn state_command(
+    _: ConsoleArgs,
+    state: Res<State<GameState>>,
+    map_name: Res<MapName>,
+    mut console: ResMut<Console>,
+) {
+    console.print(format!("{:?} on {}", state.get(), map_name.0));
+}
@@ -0,0 +1,214 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    game_over::{DeathCause, PieceKilled},
+    map::{GameMap, GridPos, MapMeta, GRID_CELL_SIZE},
+    snake::{SnakeRewound, SnakeSize},
+    sounds::SoundEvent,
+    turn::TurnEvent,
+    GameState, GameplaySet,
+};
+
+#[derive(Debug, Event)]
+pub struct FloorStepped(pub [usize; 2]);
+
+// steps is how many turns ago the snake passed over it
+#[derive(Debug, Component, Default)]
+struct CrumblingFloor {
+    steps: Option<usize>,
+}
+
+#[derive(Debug, Component)]
+struct Collapsing(Timer);
+
+pub fn crumble_plugin(app: &mut App) {
+    app.observe(on_floor_stepped)
+        .observe(on_turn_crumble)
+        .observe(on_rewind_crumble)
+        .observe(on_piece_removed)
+        .add_systems(Update, collapse_run.in_set(GameplaySet::After));
+}
+
+pub fn spawn_crumbling_floor(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(GRID_CELL_SIZE - 6.))),
+            material: materials.add(Color::srgb_u8(70, 60, 50)),
+            transform: Transform::from_xyz(0., 0., 20.),
+            ..default()
+        },
+        CrumblingFloor::default(),
+        grid_pos,
+        StateScoped(GameState::Gaming),
+    ));
+}
+
+pub fn spawn_pit(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle::new(GRID_CELL_SIZE.x / 2. - 3.))),
+            material: materials.add(Color::BLACK),
+            transform: Transform::from_xyz(0., 0., 15.),
+            ..default()
+        },
+        grid_pos,
+        StateScoped(GameState::Gaming),
+    ));
+}
+
+fn collapse(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    map: &mut GameMap,
+    floor: Entity,
+    pos: GridPos,
+    dead_piece: Option<Entity>,
+) {
+    map.get_mut(pos)
+        .expect("should be on a valid tile")
+        .collapse();
+
+    commands
+        .entity(floor)
+        .remove::<CrumblingFloor>()
+        .insert(Collapsing(Timer::from_seconds(0.4, TimerMode::Once)));
+    spawn_pit(commands, meshes, materials, pos);
+
+    if let Some(dead_piece) = dead_piece {
+        commands.trigger_targets(PieceKilled(DeathCause::Fell), dead_piece);
+    }
+
+    commands.trigger(SoundEvent::Wind);
+}
+
+fn on_floor_stepped(
+    trigger: Trigger<FloorStepped>,
+    mut floors: Query<(&mut CrumblingFloor, &GridPos)>,
+) {
+    if let Some(mut floor) = floors
+        .iter_mut()
+        .find_map(|(floor, pos)| (pos.0 == trigger.event().0).then_some(floor))
+    {
+        floor.steps.get_or_insert(0);
+    }
+}
+
+fn on_turn_crumble(
+    _: Trigger<TurnEvent>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut floors: Query<(Entity, &mut CrumblingFloor, &GridPos)>,
+    snakes: Query<(Entity, &GridPos), With<SnakeSize>>,
+    mut map: ResMut<GameMap>,
+    meta: Res<MapMeta>,
+) {
+    let Some(crumble_after) = meta.crumble_after else {
+        return;
+    };
+
+    for (ent, mut floor, pos) in floors.iter_mut() {
+        let Some(steps) = floor.steps.as_mut() else {
+            continue;
+        };
+
+        *steps += 1;
+        if *steps >= crumble_after {
+            collapse(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut map,
+                ent,
+                *pos,
+                snakes
+                    .iter()
+                    .find_map(|(piece, piece_pos)| (piece_pos == pos).then_some(piece)),
+            );
+        }
+    }
+}
+
+// with `crumble_restore::true` rewinding off a floor puts it back the way it was
+fn on_rewind_crumble(
+    trigger: Trigger<SnakeRewound>,
+    mut floors: Query<(&mut CrumblingFloor, &GridPos)>,
+    meta: Res<MapMeta>,
+) {
+    if !meta.crumble_restore {
+        return;
+    }
+
+    for (mut floor, _) in floors
+        .iter_mut()
+        .filter(|(_, pos)| trigger.event().0.contains(&pos.0))
+    {
+        floor.steps = None;
+    }
+}
+
+fn on_piece_removed(
+    trigger: Trigger<OnRemove, GridPos>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    floors: Query<(Entity, &CrumblingFloor, &GridPos)>,
+    snakes: Query<&GridPos, With<SnakeSize>>,
+    mut map: ResMut<GameMap>,
+    state: Res<State<GameState>>,
+) {
+    if *state.get() != GameState::Gaming {
+        return;
+    }
+
+    let Ok(piece_pos) = snakes.get(trigger.entity()) else {
+        return;
+    };
+
+    if let Some((ent, _, pos)) = floors
+        .iter()
+        .find(|(_, floor, pos)| floor.steps.is_some() && *pos == piece_pos)
+    {
+        collapse(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut map,
+            ent,
+            *pos,
+            None,
+        );
+    }
+}
+
+fn collapse_run(
+    mut commands: Commands,
+    mut floors: Query<(Entity, &mut Transform, &mut Collapsing)>,
+    time: Res<Time>,
+) {
+    for (ent, mut transform, mut collapsing) in floors.iter_mut() {
+        collapsing.0.tick(time.delta());
+
+        if collapsing.0.finished() {
+            commands.entity(ent).despawn_recursive();
+            continue;
+        }
+
+        transform.scale = Vec3::splat(collapsing.0.fraction_remaining());
+        transform.rotation = Quat::from_rotation_z(collapsing.0.fraction());
+    }
+}
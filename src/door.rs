@@ -1,11 +1,14 @@
 use bevy::prelude::*;
 
 use crate::{
-    map::{GameMap, GridPos},
-    snake::{CanMove, Move, SnakeIndex, SnakeSize, TaggedDeath},
+    fade_out::FadeOutThisEnt,
+    game_over::{DeathCause, PieceKilled},
+    map::{GameMap, GridPos, MapMeta, GRID_CELL_SIZE},
+    snake::{SnakeColor, SnakeId, SnakeIndex, SnakeSize},
     sounds::SoundEvent,
     title::UiResources,
-    GameState,
+    turn::TurnEvent,
+    GameState, GameplaySet,
 };
 
 #[derive(Debug, Resource)]
@@ -40,36 +43,94 @@ impl FromWorld for DoorSprites {
 #[derive(Debug, Event)]
 pub struct PressurePlateActivated(pub char, pub [usize; 2]);
 
+// opens doors and closes anti doors of a channel
+#[derive(Debug, Event)]
+pub struct ChannelActivated(pub char);
+
+// flips every door of a channel between open and closed
+#[derive(Debug, Event)]
+pub struct SwitchToggled(pub char, pub [usize; 2]);
+
 #[derive(Debug, Component)]
 pub struct PressurePlate;
 
+#[derive(Debug, Component)]
+pub struct Switch;
+
 #[derive(Debug, Component, PartialEq, Eq)]
 pub struct Door(char);
 
 #[derive(Debug, Component, PartialEq, Eq)]
 pub struct AntiDoor(char);
 
+// how long a timed door stays open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoorTimer {
+    Moves(usize),
+    Seconds(f32),
+}
+
+impl Default for DoorTimer {
+    fn default() -> Self {
+        DoorTimer::Moves(5)
+    }
+}
+
+impl DoorTimer {
+    // `door_timer::4` closes after 4 moves, `door_timer::2.5s` after 2.5 seconds
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.strip_suffix('s') {
+            Some(secs) => secs.trim().parse().ok().map(DoorTimer::Seconds),
+            None => value.parse().ok().map(DoorTimer::Moves),
+        }
+    }
+
+    fn is_up(self) -> bool {
+        match self {
+            DoorTimer::Moves(moves) => moves == 0,
+            DoorTimer::Seconds(secs) => secs <= 0.,
+        }
+    }
+}
+
+// open_for is the time left before it closes again
+#[derive(Debug, Component)]
+pub struct TimedDoor {
+    channel: char,
+    open_for: Option<DoorTimer>,
+}
+
+#[derive(Debug, Component)]
+struct DoorCountdown;
+
+// pieces a closing door can land on
+pub type CrushableSnakes<'w, 's, 'a> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'a GridPos,
+        &'a SnakeId,
+        &'a SnakeIndex,
+        &'a SnakeColor,
+    ),
+    With<SnakeSize>,
+>;
+
 pub fn door_plugin(app: &mut App) {
     app.init_resource::<DoorSprites>()
-        .observe(on_plate_activated);
+        .observe(on_plate_activated)
+        .observe(on_channel_activated)
+        .observe(on_switch_toggled)
+        .observe(on_turn_timed_doors)
+        .add_systems(Update, update_timed_doors.in_set(GameplaySet::Behavior));
 }
 
 fn on_plate_activated(
     trigger: Trigger<PressurePlateActivated>,
     mut commands: Commands,
-    doors: Query<(Entity, &Door), Without<AntiDoor>>,
-    mut anti_doors: Query<(Entity, &AntiDoor, &GridPos, &mut Sprite), Without<Door>>,
-    mut pressure_plates: Query<(&GridPos, &mut TextureAtlas), (Without<Door>, With<PressurePlate>)>,
-    mut snakes: Query<
-        (Entity, &GridPos, &mut Visibility),
-        (
-            With<SnakeSize>,
-            Without<Door>,
-            Without<AntiDoor>,
-            Without<PressurePlate>,
-        ),
-    >,
-    mut map: ResMut<GameMap>,
+    mut pressure_plates: Query<(&GridPos, &mut TextureAtlas), With<PressurePlate>>,
 ) {
     let index = &mut pressure_plates
         .iter_mut()
@@ -83,6 +144,32 @@ fn on_plate_activated(
     }
     *index = 1;
 
+    commands.trigger(ChannelActivated(trigger.event().0));
+}
+
+fn on_channel_activated(
+    trigger: Trigger<ChannelActivated>,
+    mut commands: Commands,
+    doors: Query<(Entity, &Door), Without<AntiDoor>>,
+    mut anti_doors: Query<(Entity, &AntiDoor, &GridPos, &mut Sprite), Without<Door>>,
+    mut timed_doors: Query<(&mut TimedDoor, &GridPos, &mut Sprite), Without<AntiDoor>>,
+    snakes: CrushableSnakes,
+    mut map: ResMut<GameMap>,
+    meta: Res<MapMeta>,
+) {
+    for (mut timed_door, grid_pos, mut sprite) in timed_doors
+        .iter_mut()
+        .filter(|door| door.0.channel == trigger.event().0)
+    {
+        if timed_door.open_for.is_none() {
+            map.get_mut(*grid_pos)
+                .expect("should be on a valid tile")
+                .top_removed();
+            sprite.color.set_alpha(0.3);
+        }
+        timed_door.open_for = Some(meta.door_timer);
+    }
+
     for ent in doors
         .iter()
         .filter_map(|door| door.1 .0.eq(&trigger.event().0).then_some(door.0))
@@ -96,46 +183,178 @@ fn on_plate_activated(
             .eq(&trigger.event().0)
             .then_some((door.0, door.1, door.2, door.3))
     }) {
+        close_door(
+            &mut commands,
+            &mut map,
+            &snakes,
+            ent,
+            anti_door.0,
+            *grid_pos,
+            &mut sprite,
+        );
+    }
+
+    commands.trigger(SoundEvent::PressurePlate);
+}
+
+fn on_switch_toggled(
+    trigger: Trigger<SwitchToggled>,
+    mut commands: Commands,
+    mut switches: Query<(&GridPos, &mut TextureAtlas), With<Switch>>,
+    mut doors: Query<(Entity, &Door, &GridPos, &mut Sprite), Without<AntiDoor>>,
+    mut anti_doors: Query<(Entity, &AntiDoor, &GridPos, &mut Sprite), Without<Door>>,
+    snakes: CrushableSnakes,
+    mut map: ResMut<GameMap>,
+) {
+    let SwitchToggled(channel, pos) = *trigger.event();
+
+    if let Some((_, mut atlas)) = switches.iter_mut().find(|(grid_pos, _)| grid_pos.0 == pos) {
+        atlas.index = 1 - atlas.index;
+    }
+
+    // collected first so a door that just opened doesn't get closed again
+    let opening = doors
+        .iter()
+        .filter_map(|(ent, door, ..)| (door.0 == channel).then_some(ent))
+        .collect::<Vec<_>>();
+    let closing = anti_doors
+        .iter()
+        .filter_map(|(ent, door, ..)| (door.0 == channel).then_some(ent))
+        .collect::<Vec<_>>();
+
+    for ent in opening {
+        let (_, _, grid_pos, mut sprite) = doors.get_mut(ent).expect("was just queried");
+
         commands
             .entity(ent)
-            .remove::<AntiDoor>()
-            .insert(Door(anti_door.0));
+            .remove::<Door>()
+            .insert(AntiDoor(channel));
 
         map.get_mut(*grid_pos)
             .expect("should be on a valid tile")
-            .upgrade_to_door(anti_door.0);
+            .downgrade_to_anti_door(channel);
 
-        sprite.color = Color::default();
-        sprite.flip_y = false;
-        sprite.flip_x = false;
-
-        if let Some((dead_piece, mut visibility)) = snakes
-            .iter_mut()
-            .find_map(|(ent, pos, visibility)| (*grid_pos == *pos).then_some((ent, visibility)))
-        {
-            // hack
-            commands
-                .entity(dead_piece)
-                .remove::<SnakeSize>()
-                .remove::<Move>()
-                .remove::<CanMove>()
-                .remove::<Sprite>()
-                .remove::<Handle<Image>>()
-                .remove::<SnakeIndex>()
-                .try_insert(TaggedDeath);
-
-            *visibility = Visibility::Hidden;
-        }
+        set_door_sprite(&mut sprite, false);
+    }
+
+    for ent in closing {
+        let (_, _, grid_pos, mut sprite) = anti_doors.get_mut(ent).expect("was just queried");
+
+        close_door(
+            &mut commands,
+            &mut map,
+            &snakes,
+            ent,
+            channel,
+            *grid_pos,
+            &mut sprite,
+        );
     }
 
     commands.trigger(SoundEvent::PressurePlate);
+}
 
-    pressure_plates
-        .iter_mut()
-        .find(|(pos, _)| pos.0 == trigger.event().1)
-        .expect("trigger pos should be a valid pressure plate")
-        .1
-        .index = 1;
+fn close_door(
+    commands: &mut Commands,
+    map: &mut GameMap,
+    snakes: &CrushableSnakes,
+    ent: Entity,
+    channel: char,
+    grid_pos: GridPos,
+    sprite: &mut Sprite,
+) {
+    commands
+        .entity(ent)
+        .remove::<AntiDoor>()
+        .insert(Door(channel));
+
+    map.get_mut(grid_pos)
+        .expect("should be on a valid tile")
+        .upgrade_to_door(channel);
+
+    set_door_sprite(sprite, true);
+    crush_snake_at(commands, map, snakes, grid_pos);
+}
+
+// the crushed piece is destroyed and everything behind it is severed
+pub fn crush_snake_at(
+    commands: &mut Commands,
+    map: &mut GameMap,
+    snakes: &CrushableSnakes,
+    grid_pos: GridPos,
+) {
+    let Some((_, _, &snake, &crushed, _)) = snakes.iter().find(|piece| *piece.1 == grid_pos) else {
+        return;
+    };
+
+    for (piece, piece_pos, _, index, color) in snakes
+        .iter()
+        .filter(|piece| *piece.2 == snake && piece.3 .0 <= crushed.0)
+    {
+        if index.0 == crushed.0 {
+            commands.trigger_targets(PieceKilled(DeathCause::Crushed), piece);
+            continue;
+        }
+
+        if let Some(map_tile) = map.get_mut(*piece_pos) {
+            map_tile.top_removed();
+        }
+        commands.entity(piece).insert(FadeOutThisEnt(color.0));
+    }
+}
+
+fn on_turn_timed_doors(_: Trigger<TurnEvent>, mut timed_doors: Query<&mut TimedDoor>) {
+    for mut timed_door in timed_doors.iter_mut() {
+        if let Some(DoorTimer::Moves(moves)) = timed_door.open_for.as_mut() {
+            *moves = moves.saturating_sub(1);
+        }
+    }
+}
+
+fn update_timed_doors(
+    mut commands: Commands,
+    mut timed_doors: Query<(&mut TimedDoor, &GridPos, &mut Sprite, &Children)>,
+    mut countdowns: Query<&mut Text, With<DoorCountdown>>,
+    snakes: CrushableSnakes,
+    mut map: ResMut<GameMap>,
+    time: Res<Time>,
+) {
+    for (mut timed_door, grid_pos, mut sprite, children) in timed_doors.iter_mut() {
+        if let Some(DoorTimer::Seconds(secs)) = timed_door.open_for.as_mut() {
+            *secs -= time.delta_seconds();
+        }
+
+        if timed_door.open_for.is_some_and(DoorTimer::is_up) {
+            timed_door.open_for = None;
+
+            map.get_mut(*grid_pos)
+                .expect("should be on a valid tile")
+                .close_timed_door(timed_door.channel);
+            sprite.color.set_alpha(1.);
+            crush_snake_at(&mut commands, &mut map, &snakes, *grid_pos);
+
+            commands.trigger(SoundEvent::PressurePlate);
+        }
+
+        let mut countdowns = countdowns.iter_many_mut(children);
+        while let Some(mut text) = countdowns.fetch_next() {
+            text.sections[0].value = match timed_door.open_for {
+                Some(DoorTimer::Moves(moves)) => moves.to_string(),
+                Some(DoorTimer::Seconds(secs)) => format!("{:.1}", secs.max(0.)),
+                None => String::new(),
+            };
+        }
+    }
+}
+
+fn set_door_sprite(sprite: &mut Sprite, closed: bool) {
+    if closed {
+        sprite.color = Color::default();
+    } else {
+        sprite.color = Color::srgba(0., 0., 1., 1.);
+    }
+    sprite.flip_y = !closed;
+    sprite.flip_x = !closed;
 }
 
 pub fn spawn_door(
@@ -246,3 +465,98 @@ pub fn spawn_pressure_plate(
             });
         });
 }
+
+pub fn spawn_switch(
+    commands: &mut Commands<'_, '_>,
+    door_sprites: &DoorSprites,
+    ui_resources: &UiResources,
+    grid_pos: GridPos,
+    door_char: char,
+) {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: door_sprites.pressure_plate.clone(),
+                transform: Transform::from_xyz(0., 0., 30.),
+                sprite: Sprite {
+                    color: Color::srgb(1., 0.8, 0.3),
+                    ..default()
+                },
+                ..default()
+            },
+            TextureAtlas::from(door_sprites.pressure_plate_layout.clone()),
+            Switch,
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    door_char,
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(0., 0., 32.),
+                ..default()
+            });
+        });
+}
+
+pub fn spawn_timed_door(
+    commands: &mut Commands<'_, '_>,
+    door_sprites: &DoorSprites,
+    ui_resources: &UiResources,
+    grid_pos: GridPos,
+    door_char: char,
+) {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: door_sprites.door.clone(),
+                transform: Transform::from_xyz(0., 0., 30.),
+                sprite: Sprite {
+                    color: Color::srgb(1., 0.6, 0.2),
+                    ..default()
+                },
+                ..default()
+            },
+            TimedDoor {
+                channel: door_char,
+                open_for: None,
+            },
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    door_char,
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(0., 0., 32.),
+                ..default()
+            });
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: ui_resources.font.clone(),
+                            font_size: 20.,
+                            color: Color::srgb(1., 0.6, 0.2),
+                        },
+                    ),
+                    transform: Transform::from_xyz(0., GRID_CELL_SIZE.y * 0.7, 150.),
+                    ..default()
+                },
+                DoorCountdown,
+            ));
+        });
+}
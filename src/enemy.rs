@@ -0,0 +1,168 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    game_over::{DeathCause, PieceKilled},
+    map::{GameMap, GridPos, MapMeta, GRID_CELL_SIZE},
+    snake::SnakeSize,
+    sounds::SoundEvent,
+    turn::{TurnEvent, TurnMode},
+    GameState, GameplaySet,
+};
+
+#[derive(Debug, Component)]
+pub struct Enemy {
+    path: Vec<IVec2>,
+    step: usize,
+}
+
+#[derive(Debug, Resource)]
+struct EnemyTimer(Timer);
+
+// steps the enemies still have to take, filled by the timer or by turns
+#[derive(Debug, Resource, Default)]
+struct PendingEnemySteps(usize);
+
+pub fn enemy_plugin(app: &mut App) {
+    app.insert_resource(EnemyTimer(Timer::from_seconds(0.6, TimerMode::Repeating)))
+        .init_resource::<PendingEnemySteps>()
+        .observe(on_turn_enemy)
+        .add_systems(OnEnter(GameState::Gaming), spawn_enemies)
+        .add_systems(
+            Update,
+            (
+                tick_enemy_timer,
+                step_enemies,
+                enemy_kills,
+                enemy_follow_grid,
+            )
+                .chain()
+                .in_set(GameplaySet::Behavior),
+        );
+}
+
+pub fn parse_patrol(path: &str) -> Vec<IVec2> {
+    path.chars()
+        .filter_map(|dir| match dir.to_ascii_uppercase() {
+            'U' => Some(IVec2::Y),
+            'D' => Some(IVec2::NEG_Y),
+            'L' => Some(IVec2::NEG_X),
+            'R' => Some(IVec2::X),
+            '.' => Some(IVec2::ZERO),
+            _ => None,
+        })
+        .collect()
+}
+
+fn spawn_enemies(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut pending: ResMut<PendingEnemySteps>,
+    mut timer: ResMut<EnemyTimer>,
+    meta: Res<MapMeta>,
+) {
+    pending.0 = 0;
+    timer.0.reset();
+
+    for (index, pos) in meta.enemy_starts.iter().enumerate() {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(RegularPolygon::new(20., 3))),
+                material: materials.add(Color::srgb_u8(220, 40, 40)),
+                transform: Transform::from_xyz(0., 0., 110.),
+                ..default()
+            },
+            Enemy {
+                path: meta.patrols.get(index).cloned().unwrap_or_default(),
+                step: 0,
+            },
+            GridPos(*pos),
+            StateScoped(GameState::Gaming),
+        ));
+    }
+}
+
+fn on_turn_enemy(
+    _: Trigger<TurnEvent>,
+    mode: Res<TurnMode>,
+    mut pending: ResMut<PendingEnemySteps>,
+) {
+    if *mode == TurnMode::TurnBased {
+        pending.0 += 1;
+    }
+}
+
+fn tick_enemy_timer(
+    mode: Res<TurnMode>,
+    mut timer: ResMut<EnemyTimer>,
+    mut pending: ResMut<PendingEnemySteps>,
+    time: Res<Time>,
+) {
+    if *mode != TurnMode::RealTime {
+        return;
+    }
+
+    timer.0.tick(time.delta());
+    pending.0 += timer.0.times_finished_this_tick() as usize;
+}
+
+fn step_enemies(
+    mut enemies: Query<(&mut Enemy, &mut GridPos)>,
+    mut pending: ResMut<PendingEnemySteps>,
+    map: Res<GameMap>,
+) {
+    for _ in 0..std::mem::take(&mut pending.0) {
+        for (mut enemy, mut grid_pos) in enemies.iter_mut() {
+            let Some(dir) = enemy
+                .path
+                .get(enemy.step % enemy.path.len().max(1))
+                .copied()
+            else {
+                continue;
+            };
+
+            let Some(next) = usize::try_from(grid_pos.0[0] as isize + dir.x as isize)
+                .ok()
+                .zip(usize::try_from(grid_pos.0[1] as isize + dir.y as isize).ok())
+                .map(|(x, y)| [x, y])
+            else {
+                continue;
+            };
+
+            // blocked enemies wait until the way is clear
+            if map.get(next).is_some_and(|tile| !tile.blocks_enemies()) {
+                grid_pos.0 = next;
+                enemy.step += 1;
+            }
+        }
+    }
+}
+
+fn enemy_kills(
+    mut commands: Commands,
+    enemies: Query<&GridPos, With<Enemy>>,
+    snakes: Query<(Entity, &GridPos), (With<SnakeSize>, Without<Enemy>)>,
+) {
+    for dead_piece in enemies.iter().filter_map(|enemy_pos| {
+        snakes
+            .iter()
+            .find_map(|(ent, pos)| (*enemy_pos == *pos).then_some(ent))
+    }) {
+        commands.trigger_targets(PieceKilled(DeathCause::Enemy), dead_piece);
+        commands.trigger(SoundEvent::Spike);
+    }
+}
+
+fn enemy_follow_grid(mut enemies: Query<(&mut Transform, &GridPos), With<Enemy>>, time: Res<Time>) {
+    for (mut transform, grid_pos) in enemies.iter_mut() {
+        let target = GRID_CELL_SIZE * grid_pos.to_vec2();
+        transform.translation = transform
+            .translation
+            .truncate()
+            .lerp(target, (time.delta_seconds() * 15.).min(1.))
+            .extend(transform.translation.z);
+    }
+}
@@ -5,8 +5,13 @@ use bevy::{
 };
 
 use crate::{
-    map::{GridPos, MapName, GRID_CELL_SIZE},
-    snake::{CanMove, Move, RewindCounter, TaggedDeath},
+    console::{Console, ConsoleApp, ConsoleArgs},
+    input::GamepadInput,
+    keymap::{Action, Keymap},
+    map::{level_map, level_number, GameMap, GridPos, MapMeta, MapName, Pickup, GRID_CELL_SIZE},
+    objectives::LevelStats,
+    snake::{ActiveSnake, CanMove, Move, RewindCounter, SnakeId, SnakeSize},
+    sounds::SoundEvent,
     title::UiResources,
     GameState,
 };
@@ -14,6 +19,113 @@ use crate::{
 #[derive(Debug, Event)]
 pub struct GameWinTrigger;
 
+#[derive(Debug, Event)]
+pub struct ExitReached(pub SnakeId, pub [usize; 2]);
+
+// where the exit the level was won through leads, if the map says so
+#[derive(Debug, Resource, Default)]
+pub struct NextLevel(pub Option<String>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Crushed,
+    Spiked,
+    Enemy,
+    Laser,
+    Fell,
+    OutOfRewinds,
+}
+
+impl DeathCause {
+    fn describe(self) -> &'static str {
+        match self {
+            DeathCause::Crushed => "crushed by a closing door",
+            DeathCause::Spiked => "impaled on a spike",
+            DeathCause::Enemy => "caught by an enemy",
+            DeathCause::Laser => "burned by a laser",
+            DeathCause::Fell => "fell into a pit",
+            DeathCause::OutOfRewinds => "ran out of rewinds",
+        }
+    }
+}
+
+// targets the snake piece that gets destroyed
+#[derive(Debug, Event)]
+pub struct PieceKilled(pub DeathCause);
+
+// what killed the last head, shown on the death screen
+#[derive(Debug, Resource, Default)]
+pub struct DeathReason(pub Option<DeathCause>);
+
+// extra requirements before an exit lets the snake through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCondition {
+    MinLength(usize),
+    MaxLength(usize),
+    Collected,
+    ChannelOpen(char),
+}
+
+impl ExitCondition {
+    // `length>=6`, `length<=4`, `collected` or `open::Z`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if let Some(length) = value.strip_prefix("length>=") {
+            length.trim().parse().ok().map(ExitCondition::MinLength)
+        } else if let Some(length) = value.strip_prefix("length<=") {
+            length.trim().parse().ok().map(ExitCondition::MaxLength)
+        } else if let Some(channel) = value.strip_prefix("open::") {
+            channel
+                .trim()
+                .chars()
+                .next()
+                .map(ExitCondition::ChannelOpen)
+        } else if value == "collected" {
+            Some(ExitCondition::Collected)
+        } else {
+            None
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            ExitCondition::MinLength(length) => format!("snake must be at least {length} long"),
+            ExitCondition::MaxLength(length) => format!("snake must be at most {length} long"),
+            ExitCondition::Collected => "everything must be collected".to_string(),
+            ExitCondition::ChannelOpen(channel) => format!("door {channel} must be open"),
+        }
+    }
+
+    fn is_met(self, length: usize, pickups_left: bool, map: &GameMap) -> bool {
+        match self {
+            ExitCondition::MinLength(min) => length >= min,
+            ExitCondition::MaxLength(max) => length <= max,
+            ExitCondition::Collected => !pickups_left,
+            ExitCondition::ChannelOpen(channel) => !map.has_closed_door(channel),
+        }
+    }
+}
+
+// the first exit condition the active snake doesn't meet
+#[derive(Debug, Resource, Default)]
+pub struct ExitLock(pub Option<ExitCondition>);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WinCondition {
+    #[default]
+    Any,
+    All,
+    Snake(SnakeId),
+}
+
+// every snake the level started with and the ones that made it to an exit
+#[derive(Debug, Resource, Default)]
+struct Snakes {
+    all: Vec<SnakeId>,
+    exited: Vec<SnakeId>,
+}
+
 #[derive(Debug, Default, SubStates, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 #[source(GameState = GameState::Gaming)]
 pub enum GameOverState {
@@ -28,7 +140,15 @@ pub struct Exit;
 
 pub fn game_over_plugin(app: &mut App) {
     app.add_sub_state::<GameOverState>()
+        .init_resource::<Snakes>()
+        .init_resource::<DeathReason>()
+        .init_resource::<ExitLock>()
+        .init_resource::<NextLevel>()
         .observe(on_game_won_reached)
+        .observe(on_piece_killed)
+        .observe(on_exit_reached)
+        .console_command("win", "wins the level", win_command)
+        .console_command("die", "loses the level", die_command)
         .add_systems(OnEnter(GameState::Gaming), reset_game_over)
         .add_systems(
             OnEnter(GameOverState::Death),
@@ -37,7 +157,12 @@ pub fn game_over_plugin(app: &mut App) {
         .add_systems(OnEnter(GameOverState::Win), display_right_ui)
         .add_systems(
             Update,
-            (check_for_death, continue_from_state, check_for_rewinds_left)
+            (
+                check_for_death,
+                continue_from_state,
+                check_for_rewinds_left,
+                update_exit_lock,
+            )
                 .run_if(in_state(GameState::Gaming)),
         );
 }
@@ -61,8 +186,142 @@ pub fn spawn_exit(
     ));
 }
 
-fn reset_game_over(mut game_over: ResMut<NextState<GameOverState>>) {
+fn reset_game_over(
+    mut game_over: ResMut<NextState<GameOverState>>,
+    mut snakes: ResMut<Snakes>,
+    mut reason: ResMut<DeathReason>,
+    mut lock: ResMut<ExitLock>,
+    mut next_level: ResMut<NextLevel>,
+    heads: Query<&SnakeId, With<CanMove>>,
+) {
     game_over.set(GameOverState::None);
+    reason.0 = None;
+    lock.0 = None;
+    next_level.0 = None;
+
+    snakes.all = heads.iter().copied().collect();
+    snakes.all.sort();
+    snakes.exited.clear();
+}
+
+fn unmet_exit_condition(
+    meta: &MapMeta,
+    map: &GameMap,
+    length: usize,
+    pickups_left: bool,
+) -> Option<ExitCondition> {
+    meta.exit_requires
+        .iter()
+        .copied()
+        .find(|condition| !condition.is_met(length, pickups_left, map))
+}
+
+fn update_exit_lock(
+    mut lock: ResMut<ExitLock>,
+    exits: Query<&Handle<ColorMaterial>, With<Exit>>,
+    pieces: Query<&SnakeId, With<SnakeSize>>,
+    pickups: Query<(), With<Pickup>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    active: Res<ActiveSnake>,
+    map: Res<GameMap>,
+    meta: Res<MapMeta>,
+) {
+    let length = pieces.iter().filter(|snake| **snake == active.0).count();
+    let unmet = unmet_exit_condition(&meta, &map, length, !pickups.is_empty());
+
+    if lock.0 == unmet {
+        return;
+    }
+    lock.0 = unmet;
+
+    for material in exits.iter() {
+        if let Some(material) = materials.get_mut(material.id()) {
+            material.color = if unmet.is_some() {
+                Color::srgb_u8(70, 70, 90)
+            } else {
+                Color::srgb_u8(20, 20, 200)
+            };
+        }
+    }
+}
+
+fn on_exit_reached(
+    trigger: Trigger<ExitReached>,
+    mut commands: Commands,
+    mut snakes: ResMut<Snakes>,
+    mut next_level: ResMut<NextLevel>,
+    pieces: Query<&SnakeId, With<SnakeSize>>,
+    pickups: Query<(), With<Pickup>>,
+    map: Res<GameMap>,
+    meta: Res<MapMeta>,
+) {
+    let ExitReached(snake, exit) = *trigger.event();
+
+    let length = pieces.iter().filter(|piece| **piece == snake).count();
+    if unmet_exit_condition(&meta, &map, length, !pickups.is_empty()).is_some() {
+        return;
+    }
+    commands.trigger(SoundEvent::Exit);
+
+    if !snakes.exited.contains(&snake) {
+        snakes.exited.push(snake);
+    }
+
+    let won = match meta.win {
+        WinCondition::Any => true,
+        WinCondition::All => snakes.all.iter().all(|snake| snakes.exited.contains(snake)),
+        WinCondition::Snake(required) => snake == required,
+    };
+
+    if won {
+        next_level.0 = meta.destination_of(exit).cloned();
+        commands.trigger(GameWinTrigger);
+    }
+}
+
+fn on_piece_killed(
+    trigger: Trigger<PieceKilled>,
+    mut commands: Commands,
+    heads: Query<(), Or<(With<CanMove>, With<Move>)>>,
+    mut reason: ResMut<DeathReason>,
+) {
+    let piece = trigger.entity();
+
+    if heads.contains(piece) {
+        reason.0 = Some(trigger.event().0);
+    }
+
+    if let Some(piece) = commands.get_entity(piece) {
+        piece.despawn_recursive();
+    }
+}
+
+fn win_command(
+    _: ConsoleArgs,
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut console: ResMut<Console>,
+) {
+    if *state.get() != GameState::Gaming {
+        console.print("not in a level");
+        return;
+    }
+
+    commands.trigger(GameWinTrigger);
+}
+
+fn die_command(
+    _: ConsoleArgs,
+    state: Res<State<GameState>>,
+    mut game_over: ResMut<NextState<GameOverState>>,
+    mut console: ResMut<Console>,
+) {
+    if *state.get() != GameState::Gaming {
+        console.print("not in a level");
+        return;
+    }
+
+    game_over.set(GameOverState::Death);
 }
 
 fn on_game_won_reached(
@@ -73,19 +332,34 @@ fn on_game_won_reached(
 }
 
 fn check_for_death(
-    snake: Query<Entity, (Or<(With<CanMove>, With<Move>)>, Without<TaggedDeath>)>,
+    snake: Query<&SnakeId, Or<(With<CanMove>, With<Move>)>>,
+    snakes: Res<Snakes>,
+    meta: Res<MapMeta>,
     mut game_over: ResMut<NextState<GameOverState>>,
 ) {
-    if snake.iter().next().is_none() {
+    let alive = |required: &SnakeId| snake.iter().any(|snake| snake == required);
+
+    let required_dead = match meta.win {
+        WinCondition::Any => false,
+        WinCondition::All => snakes
+            .all
+            .iter()
+            .any(|required| !snakes.exited.contains(required) && !alive(required)),
+        WinCondition::Snake(required) => !alive(&required),
+    };
+
+    if snake.iter().next().is_none() || required_dead {
         game_over.set(GameOverState::Death);
     }
 }
 
 fn check_for_rewinds_left(
     rewinds: Res<RewindCounter>,
+    mut reason: ResMut<DeathReason>,
     mut game_over: ResMut<NextState<GameOverState>>,
 ) {
     if rewinds.total < 0 || rewinds.individual < 0 {
+        reason.0 = Some(DeathCause::OutOfRewinds);
         game_over.set(GameOverState::Death);
     }
 }
@@ -103,6 +377,10 @@ fn display_right_ui(
     game_over: Res<State<GameOverState>>,
     mut commands: Commands,
     ui_resources: Res<UiResources>,
+    stats: Res<LevelStats>,
+    rewinds: Res<RewindCounter>,
+    meta: Res<MapMeta>,
+    reason: Res<DeathReason>,
 ) {
     let (condition_text, tip, state) = match game_over.get() {
         GameOverState::None => return,
@@ -154,49 +432,99 @@ fn display_right_ui(
         })
         .insert(StateScoped(GameState::Gaming))
         .insert(StateScoped(state));
+
+    let details = if state == GameOverState::Win {
+        objectives_text(&stats, &rewinds, &meta)
+    } else if let Some(cause) = reason.0 {
+        format!("the snake {}", cause.describe())
+    } else {
+        return;
+    };
+
+    commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                details,
+                TextStyle {
+                    font: ui_resources.font.clone(),
+                    font_size: 20.,
+                    color: Color::srgba(0.99, 0.99, 0.99, 1.),
+                },
+            ),
+            style: Style {
+                align_self: AlignSelf::Center,
+                top: Val::Vh(15.),
+                margin: UiRect::all(Val::Auto),
+                position_type: PositionType::Relative,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(StateScoped(GameState::Gaming))
+        .insert(StateScoped(state));
+}
+
+fn objectives_text(stats: &LevelStats, rewinds: &RewindCounter, meta: &MapMeta) -> String {
+    let mut objectives = Vec::new();
+    if stats.stars_total != 0 {
+        objectives.push(format!("stars {}/{}", stats.stars, stats.stars_total));
+    }
+    if let (Some(par), Some(par_met)) = (meta.par, stats.par_met(meta)) {
+        objectives.push(format!(
+            "par {par} {} ({} moves)",
+            if par_met { "met" } else { "missed" },
+            stats.moves
+        ));
+    }
+    objectives.push(format!(
+        "rewinds left {} / {}",
+        rewinds.total.max(0),
+        rewinds.individual.max(0)
+    ));
+
+    objectives.join("    ")
 }
 
 fn continue_from_state(
     keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    gamepad: GamepadInput,
     game_over: Res<State<GameOverState>>,
     mut next_state: ResMut<NextState<GameState>>,
     mut map_name: ResMut<MapName>,
+    next_level: Res<NextLevel>,
 ) {
-    if keys.just_pressed(KeyCode::KeyR) && keys.pressed(KeyCode::ControlLeft) {
+    if keymap.just_pressed(Action::Restart, &keys)
+        || gamepad.just_pressed(GamepadButtonType::Select)
+    {
         next_state.set(GameState::Loading)
     }
 
-    if keys.just_pressed(KeyCode::Escape) {
+    // mid level the menu key opens the pause menu instead
+    if *game_over.get() != GameOverState::None
+        && (keymap.just_pressed(Action::Menu, &keys)
+            || gamepad.just_pressed(GamepadButtonType::Start))
+    {
         next_state.set(GameState::MainMenu)
     }
 
-    let state = if keys.just_pressed(KeyCode::KeyN)
-        && keys.pressed(KeyCode::ControlLeft)
-        && keys.pressed(KeyCode::AltLeft)
-        && keys.pressed(KeyCode::ShiftLeft)
+    if !keymap.just_pressed(Action::Confirm, &keys)
+        && !gamepad.just_pressed(GamepadButtonType::South)
     {
-        GameOverState::Win
-    } else if !keys.just_pressed(KeyCode::Enter) {
         return;
-    } else {
-        *game_over.get()
-    };
+    }
 
-    match state {
+    match *game_over.get() {
         GameOverState::None => {}
         GameOverState::Death => next_state.set(GameState::Loading),
         GameOverState::Win => {
-            if let Some(map_index) = map_name
-                .0
-                .split_once('.')
-                .map(|(file_name, _)| file_name)
-                .and_then(|file_name| file_name.split_once('_'))
-                .map(|(_, num)| num)
-                .and_then(|last| last.parse::<u32>().ok())
+            if let Some(destination) = next_level.0.clone() {
+                map_name.0 = destination;
+                next_state.set(GameState::Loading);
+            } else if let Some(next) =
+                level_number(&map_name.0).and_then(|level| level_map(level + 1))
             {
-                map_name
-                    .0
-                    .replace_range(.., &format!("maps/map_{}.game_map", map_index + 1));
+                map_name.0 = next;
 
                 next_state.set(GameState::Loading);
             } else {
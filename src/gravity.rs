@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_over::{DeathCause, PieceKilled},
+    map::{GameMap, GridPos, MapMeta, GRID_CELL_SIZE},
+    snake::{CanMove, Move, Sliding, SnakeId, SnakeSize},
+    sounds::SoundEvent,
+    GameplaySet,
+};
+
+pub fn gravity_plugin(app: &mut App) {
+    app.add_systems(Update, apply_gravity.in_set(GameplaySet::After));
+}
+
+pub fn parse_gravity(dir: &str) -> Option<IVec2> {
+    match dir.trim() {
+        "down" => Some(IVec2::NEG_Y),
+        "up" => Some(IVec2::Y),
+        "left" => Some(IVec2::NEG_X),
+        "right" => Some(IVec2::X),
+        _ => None,
+    }
+}
+
+fn offset(pos: [usize; 2], dir: IVec2) -> Option<[usize; 2]> {
+    Some([
+        usize::try_from(pos[0] as isize + dir.x as isize).ok()?,
+        usize::try_from(pos[1] as isize + dir.y as isize).ok()?,
+    ])
+}
+
+// a snake falls as a whole until one of its pieces rests on something that isn't itself
+fn apply_gravity(
+    mut commands: Commands,
+    mut pieces: Query<
+        (Entity, &mut GridPos, &mut Transform, &SnakeId, Has<CanMove>),
+        With<SnakeSize>,
+    >,
+    moving: Query<(), Or<(With<Move>, With<Sliding>)>>,
+    mut map: ResMut<GameMap>,
+    meta: Res<MapMeta>,
+) {
+    let Some(gravity) = meta.gravity else {
+        return;
+    };
+
+    if !moving.is_empty() {
+        return;
+    }
+
+    let mut snakes = pieces.iter().map(|piece| *piece.3).collect::<Vec<_>>();
+    snakes.sort();
+    snakes.dedup();
+
+    for snake in snakes {
+        let mut fallen = false;
+        loop {
+            let below = pieces
+                .iter()
+                .filter(|piece| *piece.3 == snake)
+                .map(|piece| offset(piece.1 .0, gravity))
+                .collect::<Vec<_>>();
+
+            if below
+                .iter()
+                .flatten()
+                .any(|pos| map.get(*pos).is_some_and(|tile| tile.supports(snake)))
+            {
+                break;
+            }
+
+            // falling out of the level or into a pit
+            if below.iter().any(|pos| {
+                pos.and_then(|pos| map.get(pos))
+                    .is_none_or(|tile| tile.is_pit())
+            }) {
+                for (head, ..) in pieces.iter().filter(|piece| *piece.3 == snake && piece.4) {
+                    commands.trigger_targets(PieceKilled(DeathCause::Fell), head);
+                }
+                break;
+            }
+
+            for (_, grid_pos, ..) in pieces.iter().filter(|piece| *piece.3 == snake) {
+                if let Some(tile) = map.get_mut(*grid_pos) {
+                    tile.snake_removed();
+                }
+            }
+
+            for (_, mut grid_pos, ..) in pieces.iter_mut().filter(|piece| *piece.3 == snake) {
+                grid_pos.0 = offset(grid_pos.0, gravity).expect("checked to be in the level");
+                map.get_mut(*grid_pos)
+                    .expect("checked to be in the level")
+                    .hold_snake(snake);
+            }
+
+            fallen = true;
+        }
+
+        if !fallen {
+            continue;
+        }
+
+        for (_, grid_pos, mut transform, ..) in pieces.iter_mut().filter(|piece| *piece.3 == snake)
+        {
+            transform.translation =
+                (GRID_CELL_SIZE * grid_pos.to_vec2()).extend(transform.translation.z);
+        }
+        commands.trigger(SoundEvent::Wind);
+    }
+}
@@ -0,0 +1,31 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    map::{GridPos, GRID_CELL_SIZE},
+    GameState,
+};
+
+#[derive(Debug, Component)]
+pub struct Ice;
+
+pub fn spawn_ice(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(GRID_CELL_SIZE))),
+            material: materials.add(Color::srgb_u8(150, 200, 230)),
+            transform: Transform::from_xyz(0., 0., 10.),
+            ..default()
+        },
+        Ice,
+        grid_pos,
+        StateScoped(GameState::Gaming),
+    ));
+}
@@ -1,65 +1,299 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, log, prelude::*};
+use std::collections::VecDeque;
 
-use crate::{snake::CanMove, GameplaySet};
+use crate::{
+    keymap::{Action, Keymap},
+    rewind::RewindMode,
+    snake::{ActiveSnake, CanMove, Sliding, SnakeId},
+    GameState, GameplaySet,
+};
 
 #[derive(Debug, Event)]
 pub struct MoveEvent(pub Vec2);
 
-#[derive(Debug, Resource)]
-pub struct KeyBuffer(pub Vec<KeyCode>);
+#[derive(Debug, Event)]
+pub struct SwitchSnake;
+
+const STICK_DEADZONE: f32 = 0.5;
+
+const MAX_QUEUED_MOVES: usize = 3;
+
+const MOVES: [(Action, IVec2); 4] = [
+    (Action::MoveUp, IVec2::Y),
+    (Action::MoveDown, IVec2::NEG_Y),
+    (Action::MoveLeft, IVec2::NEG_X),
+    (Action::MoveRight, IVec2::X),
+];
+
+// directions waiting for the active head to be free, one is used per step
+#[derive(Debug, Resource, Default)]
+pub struct MoveQueue(pub VecDeque<IVec2>);
+
+impl MoveQueue {
+    pub fn push(&mut self, dir: IVec2) {
+        if self.0.len() < MAX_QUEUED_MOVES {
+            self.0.push_back(dir);
+        }
+    }
+}
+
+// in seconds, how long a direction is held before it repeats and how often it does after
+#[derive(Debug, Resource, Clone, Copy)]
+pub struct KeyRepeat {
+    pub delay: f32,
+    pub interval: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: 0.25,
+            interval: 0.1,
+        }
+    }
+}
+
+// the held direction and when it repeats next
+#[derive(Debug, Default)]
+struct HeldMove(Option<(IVec2, f32)>);
+
+impl HeldMove {
+    fn update(
+        &mut self,
+        queue: &mut MoveQueue,
+        pressed: Option<IVec2>,
+        held: impl Fn(IVec2) -> bool,
+        now: f32,
+        repeat: KeyRepeat,
+    ) {
+        if let Some(dir) = pressed {
+            queue.push(dir);
+            self.0 = Some((dir, now + repeat.delay));
+            return;
+        }
+
+        match self.0 {
+            // repeats wait for the queue so holding never runs ahead of the snake
+            Some((dir, next)) if held(dir) => {
+                if now >= next && queue.0.is_empty() {
+                    queue.push(dir);
+                    self.0 = Some((dir, now + repeat.interval));
+                }
+            }
+            _ => self.0 = None,
+        }
+    }
+}
+
+// any connected gamepad counts
+#[derive(SystemParam)]
+pub struct GamepadInput<'w> {
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl GamepadInput<'_> {
+    pub fn just_pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.buttons
+                .just_pressed(GamepadButton::new(gamepad, button))
+        })
+    }
+
+    pub fn pressed(&self, button: GamepadButtonType) -> bool {
+        self.gamepads
+            .iter()
+            .any(|gamepad| self.buttons.pressed(GamepadButton::new(gamepad, button)))
+    }
+
+    // d-pad first then the left stick, snapped to the strongest axis
+    pub fn direction(&self) -> Option<IVec2> {
+        let dpad = IVec2::new(
+            self.pressed(GamepadButtonType::DPadRight) as i32
+                - self.pressed(GamepadButtonType::DPadLeft) as i32,
+            self.pressed(GamepadButtonType::DPadUp) as i32
+                - self.pressed(GamepadButtonType::DPadDown) as i32,
+        );
+        if dpad.x != 0 && dpad.y == 0 || dpad.x == 0 && dpad.y != 0 {
+            return Some(dpad);
+        }
+
+        let stick = self
+            .gamepads
+            .iter()
+            .map(|gamepad| {
+                Vec2::new(
+                    self.axes
+                        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                        .unwrap_or_default(),
+                    self.axes
+                        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                        .unwrap_or_default(),
+                )
+            })
+            .find(|stick| stick.length() > STICK_DEADZONE)?;
+
+        Some(if stick.x.abs() > stick.y.abs() {
+            IVec2::new(stick.x.signum() as i32, 0)
+        } else {
+            IVec2::new(0, stick.y.signum() as i32)
+        })
+    }
+}
 
 pub fn input_plugin(app: &mut App) {
-    app.insert_resource(KeyBuffer(Vec::new()))
+    app.init_resource::<MoveQueue>()
+        .init_resource::<KeyRepeat>()
         .add_event::<MoveEvent>()
-        .add_systems(Update, handle_keys.in_set(GameplaySet::Input));
+        .add_event::<SwitchSnake>()
+        .add_systems(
+            OnEnter(GameState::Gaming),
+            |mut queue: ResMut<MoveQueue>| queue.0.clear(),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    queue_key_moves,
+                    queue_gamepad_moves,
+                    handle_keys,
+                    handle_gamepad,
+                    uwu,
+                ),
+                send_queued_move,
+            )
+                .chain()
+                .in_set(GameplaySet::Input),
+        );
 }
 
-fn handle_keys(
+// moves are queued even while the head is still walking so quick taps aren't lost
+fn queue_key_moves(
     keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    repeat: Res<KeyRepeat>,
+    time: Res<Time>,
+    mut queue: ResMut<MoveQueue>,
+    mut held: Local<HeldMove>,
+    rewind: Res<RewindMode>,
+) {
+    // moving scrubs the rewind instead
+    if rewind.amount.is_some() {
+        held.0 = None;
+        return;
+    }
+
+    let pressed = MOVES
+        .into_iter()
+        .find(|(action, _)| keymap.just_pressed(*action, &keys))
+        .map(|(_, dir)| dir);
+
+    held.update(
+        &mut queue,
+        pressed,
+        |dir| {
+            MOVES
+                .into_iter()
+                .any(|(action, move_dir)| move_dir == dir && keymap.pressed(action, &keys))
+        },
+        time.elapsed_seconds(),
+        *repeat,
+    );
+}
+
+fn queue_gamepad_moves(
+    gamepad: GamepadInput,
+    repeat: Res<KeyRepeat>,
+    time: Res<Time>,
+    mut queue: ResMut<MoveQueue>,
+    mut held: Local<HeldMove>,
+    mut last_dir: Local<Option<IVec2>>,
+    rewind: Res<RewindMode>,
+) {
+    let dir = gamepad.direction();
+    let pressed = dir.filter(|_| dir != *last_dir);
+    *last_dir = dir;
+
+    if rewind.amount.is_some() {
+        held.0 = None;
+        return;
+    }
+
+    held.update(
+        &mut queue,
+        pressed,
+        |held| dir == Some(held),
+        time.elapsed_seconds(),
+        *repeat,
+    );
+}
+
+fn send_queued_move(
     mut move_event: EventWriter<MoveEvent>,
-    mut cycle_buffer: ResMut<KeyBuffer>,
-    snake_pieces: Query<Entity, With<CanMove>>,
+    mut queue: ResMut<MoveQueue>,
+    heads: Query<&SnakeId, (With<CanMove>, Without<Sliding>)>,
+    active: Res<ActiveSnake>,
+    rewind: Res<RewindMode>,
 ) {
-    if snake_pieces.iter().next().is_none() {
+    if !heads.iter().any(|snake| *snake == active.0) || rewind.amount.is_some() {
         return;
     }
 
-    let move_dir = [
-        keys.pressed(KeyCode::ArrowRight) as i32 as f32
-            - keys.pressed(KeyCode::ArrowLeft) as i32 as f32,
-        keys.pressed(KeyCode::ArrowUp) as i32 as f32
-            - keys.pressed(KeyCode::ArrowDown) as i32 as f32,
-    ];
+    if let Some(dir) = queue.0.pop_front() {
+        move_event.send(MoveEvent(dir.as_vec2()));
+    }
+}
 
-    if !(move_dir[0] == 0. && move_dir[1] == 0.) && move_dir[0] != move_dir[1] {
-        move_event.send(MoveEvent(Vec2::from_array(move_dir)));
+fn handle_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    mut switch_event: EventWriter<SwitchSnake>,
+    mut queue: ResMut<MoveQueue>,
+    snake_pieces: Query<&SnakeId, With<CanMove>>,
+    active: Res<ActiveSnake>,
+) {
+    if !snake_pieces.iter().any(|snake| *snake == active.0) {
+        return;
     }
 
-    if keys.just_pressed(KeyCode::Backspace) {
-        cycle_buffer.0.clear()
+    // queued moves were meant for the old snake
+    if keymap.just_pressed(Action::SwitchSnake, &keys) {
+        switch_event.send(SwitchSnake);
+        queue.0.clear();
     }
+}
 
-    cycle_buffer.0.extend(
+fn handle_gamepad(
+    gamepad: GamepadInput,
+    mut switch_event: EventWriter<SwitchSnake>,
+    mut queue: ResMut<MoveQueue>,
+    snake_pieces: Query<&SnakeId, With<CanMove>>,
+    active: Res<ActiveSnake>,
+) {
+    if !snake_pieces.iter().any(|snake| *snake == active.0) {
+        return;
+    }
+
+    if gamepad.just_pressed(GamepadButtonType::North) {
+        switch_event.send(SwitchSnake);
+        queue.0.clear();
+    }
+}
+
+fn uwu(keys: Res<ButtonInput<KeyCode>>, keymap: Res<Keymap>, mut typed: Local<Vec<KeyCode>>) {
+    typed.extend(
         keys.get_just_pressed()
-            .map(|key| key.to_owned())
-            .filter(|key| {
-                matches!(
-                    *key,
-                    KeyCode::Digit0
-                        | KeyCode::Digit1
-                        | KeyCode::Digit2
-                        | KeyCode::Digit3
-                        | KeyCode::Digit4
-                        | KeyCode::Digit5
-                        | KeyCode::Digit6
-                        | KeyCode::Digit7
-                        | KeyCode::Digit8
-                        | KeyCode::Digit9
-                        | KeyCode::KeyC
-                        | KeyCode::KeyW
-                        | KeyCode::KeyU
-                        | KeyCode::Enter
-                )
-            }),
-    )
+            .filter(|key| matches!(key, KeyCode::KeyU | KeyCode::KeyW)),
+    );
+    let start = typed.len().saturating_sub(3);
+    typed.drain(..start);
+
+    if keymap.just_pressed(Action::Confirm, &keys) {
+        if typed[..] == [KeyCode::KeyU, KeyCode::KeyW, KeyCode::KeyU] {
+            // uwu
+            log::info!("uwu");
+        }
+        typed.clear();
+    }
 }
@@ -0,0 +1,296 @@
+use bevy::{log, prelude::*};
+use std::collections::HashMap;
+
+use crate::save::SaveBackend;
+
+const KEYMAP_NAME: &str = "keymap.cfg";
+
+// keys that can show up in the keymap file
+pub const BINDABLE: [KeyCode; 55] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::Delete,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Backquote,
+];
+
+pub const MODIFIERS: [KeyCode; 6] = [
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    SwitchSnake,
+    Rewind,
+    ClearRewind,
+    Confirm,
+    Restart,
+    Menu,
+    Console,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SwitchSnake,
+        Action::Rewind,
+        Action::ClearRewind,
+        Action::Confirm,
+        Action::Restart,
+        Action::Menu,
+        Action::Console,
+    ];
+
+    // name used in the keymap file
+    fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SwitchSnake => "switch_snake",
+            Action::Rewind => "rewind",
+            Action::ClearRewind => "clear_rewind",
+            Action::Confirm => "confirm",
+            Action::Restart => "restart",
+            Action::Menu => "menu",
+            Action::Console => "console",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move Up",
+            Action::MoveDown => "Move Down",
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::SwitchSnake => "Switch Snake",
+            Action::Rewind => "Rewind",
+            Action::ClearRewind => "Clear Rewind",
+            Action::Confirm => "Confirm",
+            Action::Restart => "Restart",
+            Action::Menu => "Menu",
+            Action::Console => "Console",
+        }
+    }
+}
+
+// every key has to be held, the last one being the one that fires it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding(pub Vec<KeyCode>);
+
+impl Binding {
+    fn parse(value: &str) -> Option<Self> {
+        value
+            .split('+')
+            .map(|key| {
+                BINDABLE
+                    .iter()
+                    .find(|bindable| format!("{bindable:?}") == key.trim())
+                    .copied()
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|keys| !keys.is_empty())
+            .map(Binding)
+    }
+
+    pub fn describe(&self) -> String {
+        self.0
+            .iter()
+            .map(|key| format!("{key:?}"))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Arrows,
+    Wasd,
+    Hjkl,
+}
+
+impl Preset {
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::Arrows => "Arrows",
+            Preset::Wasd => "WASD",
+            Preset::Hjkl => "HJKL",
+        }
+    }
+}
+
+#[derive(Debug, Resource, Clone, PartialEq, Eq)]
+pub struct Keymap(pub HashMap<Action, Binding>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Keymap(HashMap::from_iter([
+            (Action::SwitchSnake, Binding(vec![KeyCode::Tab])),
+            (Action::Rewind, Binding(vec![KeyCode::KeyC])),
+            (Action::ClearRewind, Binding(vec![KeyCode::Backspace])),
+            (Action::Confirm, Binding(vec![KeyCode::Enter])),
+            (
+                Action::Restart,
+                Binding(vec![KeyCode::ControlLeft, KeyCode::KeyR]),
+            ),
+            (Action::Menu, Binding(vec![KeyCode::Escape])),
+            (Action::Console, Binding(vec![KeyCode::Backquote])),
+        ]));
+        keymap.apply_preset(Preset::Arrows);
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn pressed(&self, action: Action, keys: &ButtonInput<KeyCode>) -> bool {
+        self.0
+            .get(&action)
+            .is_some_and(|binding| keys.all_pressed(binding.0.iter().copied()))
+    }
+
+    pub fn just_pressed(&self, action: Action, keys: &ButtonInput<KeyCode>) -> bool {
+        self.0.get(&action).is_some_and(|binding| {
+            binding.0.last().is_some_and(|key| keys.just_pressed(*key))
+                && keys.all_pressed(binding.0.iter().copied())
+        })
+    }
+
+    pub fn apply_preset(&mut self, preset: Preset) {
+        let [up, down, left, right] = match preset {
+            Preset::Arrows => [
+                KeyCode::ArrowUp,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowRight,
+            ],
+            Preset::Wasd => [KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyA, KeyCode::KeyD],
+            Preset::Hjkl => [KeyCode::KeyK, KeyCode::KeyJ, KeyCode::KeyH, KeyCode::KeyL],
+        };
+
+        for (action, key) in [
+            (Action::MoveUp, up),
+            (Action::MoveDown, down),
+            (Action::MoveLeft, left),
+            (Action::MoveRight, right),
+        ] {
+            self.0.insert(action, Binding(vec![key]));
+        }
+    }
+
+    // same `key::value` lines as the map metadata, unknown lines are skipped
+    fn from_config(config: &str) -> Self {
+        config
+            .lines()
+            .filter_map(|line| line.split_once("::"))
+            .fold(Self::default(), |mut keymap, (name, value)| {
+                if let Some((action, binding)) = Action::ALL
+                    .into_iter()
+                    .find(|action| action.name() == name.trim())
+                    .zip(Binding::parse(value))
+                {
+                    keymap.0.insert(action, binding);
+                }
+                keymap
+            })
+    }
+
+    fn to_config(&self) -> String {
+        Action::ALL
+            .into_iter()
+            .filter_map(|action| {
+                Some(format!(
+                    "{}::{}\n",
+                    action.name(),
+                    self.0.get(&action)?.describe()
+                ))
+            })
+            .collect()
+    }
+}
+
+pub fn keymap_plugin(app: &mut App) {
+    app.init_resource::<SaveBackend>();
+    let keymap = load_keymap(app.world().resource::<SaveBackend>());
+
+    app.insert_resource(keymap).add_systems(Update, save_keymap);
+}
+
+fn load_keymap(backend: &SaveBackend) -> Keymap {
+    match backend.0.read(KEYMAP_NAME) {
+        Some(config) => Keymap::from_config(&config),
+        None => Keymap::default(),
+    }
+}
+
+fn save_keymap(backend: Res<SaveBackend>, keymap: Res<Keymap>) {
+    if !keymap.is_changed() || keymap.is_added() {
+        return;
+    }
+
+    if let Err(err) = backend.0.write(KEYMAP_NAME, &keymap.to_config()) {
+        log::warn!("couldn't save the keymap: {err}");
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    map::{BottomTileType, GameMap, GridPos, Pickup, GRID_CELL_SIZE},
+    snake::{SnakeId, SnakeRewound},
+    sounds::SoundEvent,
+    title::UiResources,
+    GameState,
+};
+
+#[derive(Debug, Event)]
+pub struct KeyPickedUp(pub char, pub [usize; 2], pub SnakeId);
+
+#[derive(Debug, Event)]
+pub struct LockedDoorBumped(pub char, pub [usize; 2], pub SnakeId);
+
+#[derive(Debug, Component)]
+struct KeyItem;
+
+#[derive(Debug, Component)]
+struct LockedDoor;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeldKey {
+    pub key: char,
+    pub picked_at: [usize; 2],
+    pub snake: SnakeId,
+    // the locked door it was spent on
+    pub opened: Option<[usize; 2]>,
+}
+
+#[derive(Debug, Resource, Default)]
+pub struct Keyring(pub Vec<HeldKey>);
+
+pub fn keys_plugin(app: &mut App) {
+    app.init_resource::<Keyring>()
+        .observe(on_key_picked_up)
+        .observe(on_locked_door_bumped)
+        .observe(on_rewind_return_keys)
+        .add_systems(
+            OnEnter(GameState::Gaming),
+            |mut keyring: ResMut<Keyring>| keyring.0.clear(),
+        );
+}
+
+pub fn key_color(key: char) -> Color {
+    match key {
+        'K' => Color::srgb_u8(230, 190, 60),
+        'L' => Color::srgb_u8(190, 190, 210),
+        'O' => Color::srgb_u8(200, 120, 70),
+        _ => Color::srgb_u8(80, 200, 120),
+    }
+}
+
+pub fn spawn_key(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    ui_resources: &UiResources,
+    grid_pos: GridPos,
+    key: char,
+) {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::new(30., 12.))),
+                material: materials.add(key_color(key)),
+                transform: Transform::from_xyz(0., 0., 40.),
+                ..default()
+            },
+            KeyItem,
+            Pickup,
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    key,
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 15.,
+                        color: Color::BLACK,
+                    },
+                ),
+                transform: Transform::from_xyz(0., 0., 1.),
+                ..default()
+            });
+        });
+}
+
+pub fn spawn_locked_door(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    ui_resources: &UiResources,
+    grid_pos: GridPos,
+    key: char,
+) {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(GRID_CELL_SIZE))),
+                material: materials.add(key_color(key).darker(0.3)),
+                transform: Transform::from_xyz(0., 0., 30.),
+                ..default()
+            },
+            LockedDoor,
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    key,
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(0., 0., 2.),
+                ..default()
+            });
+        });
+}
+
+fn on_key_picked_up(
+    trigger: Trigger<KeyPickedUp>,
+    mut commands: Commands,
+    keys: Query<(Entity, &GridPos), With<KeyItem>>,
+    mut keyring: ResMut<Keyring>,
+) {
+    let KeyPickedUp(key, pos, snake) = *trigger.event();
+
+    if let Some(ent) = keys
+        .iter()
+        .find_map(|(ent, grid_pos)| (grid_pos.0 == pos).then_some(ent))
+    {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    keyring.0.push(HeldKey {
+        key,
+        picked_at: pos,
+        snake,
+        opened: None,
+    });
+    commands.trigger(SoundEvent::Pellet);
+}
+
+fn on_locked_door_bumped(
+    trigger: Trigger<LockedDoorBumped>,
+    mut commands: Commands,
+    doors: Query<(Entity, &GridPos), With<LockedDoor>>,
+    mut keyring: ResMut<Keyring>,
+) {
+    let LockedDoorBumped(key, pos, snake) = *trigger.event();
+
+    let Some(held) = keyring
+        .0
+        .iter_mut()
+        .find(|held| held.key == key && held.snake == snake && held.opened.is_none())
+    else {
+        return;
+    };
+    held.opened = Some(pos);
+
+    // the map tile gets freed when the door's grid pos is removed
+    if let Some(ent) = doors
+        .iter()
+        .find_map(|(ent, grid_pos)| (grid_pos.0 == pos).then_some(ent))
+    {
+        commands.entity(ent).despawn_recursive();
+    }
+    commands.trigger(SoundEvent::PressurePlate);
+}
+
+// keys go back where they were found, spent ones lock their door again on the way
+fn on_rewind_return_keys(
+    trigger: Trigger<SnakeRewound>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    ui_resources: Res<UiResources>,
+    mut keyring: ResMut<Keyring>,
+    mut map: ResMut<GameMap>,
+) {
+    let rewound = &trigger.event().0;
+
+    keyring.0.retain(|held| {
+        if !rewound.contains(&held.picked_at) {
+            return true;
+        }
+
+        if let Some(door) = held.opened {
+            // something else sits in the doorway now, the key stays spent
+            let Some(tile) = map.get_mut(door).filter(|tile| !tile.is_occupied()) else {
+                return true;
+            };
+            tile.relock(held.key);
+            spawn_locked_door(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &ui_resources,
+                GridPos(door),
+                held.key,
+            );
+        }
+
+        if let Some(tile) = map.get_mut(held.picked_at) {
+            tile.set_bottom(BottomTileType::Key(held.key));
+        }
+        spawn_key(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &ui_resources,
+            GridPos(held.picked_at),
+            held.key,
+        );
+        false
+    });
+}
@@ -0,0 +1,199 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    door::ChannelActivated,
+    enemy::Enemy,
+    game_over::{DeathCause, PieceKilled},
+    map::{GameMap, GridPos, GRID_CELL_SIZE},
+    snake::{CanMove, Move, SnakeSize},
+    sounds::SoundEvent,
+    title::UiResources,
+    GameState, GameplaySet,
+};
+
+const BEAM_WIDTH: f32 = 8.;
+
+#[derive(Debug, Component)]
+pub struct Emitter(IVec2);
+
+#[derive(Debug, Component)]
+struct LaserBeam;
+
+#[derive(Debug, Component)]
+pub struct Receiver {
+    channel: char,
+    powered: bool,
+}
+
+pub fn laser_plugin(app: &mut App) {
+    app.add_systems(Update, update_lasers.in_set(GameplaySet::Behavior));
+}
+
+pub fn spawn_emitter(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+    dir: IVec2,
+) {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(GRID_CELL_SIZE))),
+                material: materials.add(Color::srgb_u8(60, 20, 20)),
+                transform: Transform::from_xyz(0., 0., 30.),
+                ..default()
+            },
+            Emitter(dir),
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::new(10., 10.))),
+                material: materials.add(Color::srgb(4., 0.5, 0.5)),
+                transform: Transform::from_translation(
+                    (dir.as_vec2() * GRID_CELL_SIZE / 3.).extend(1.),
+                ),
+                ..default()
+            });
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgb(4., 0.5, 0.5),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0., 0., 20.),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                LaserBeam,
+            ));
+        });
+}
+
+pub fn spawn_receiver(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    ui_resources: &UiResources,
+    grid_pos: GridPos,
+    channel: char,
+) {
+    commands
+        .spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(GRID_CELL_SIZE))),
+                material: materials.add(Color::srgb_u8(20, 20, 60)),
+                transform: Transform::from_xyz(0., 0., 30.),
+                ..default()
+            },
+            Receiver {
+                channel,
+                powered: false,
+            },
+            grid_pos,
+            StateScoped(GameState::Gaming),
+        ))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    channel,
+                    TextStyle {
+                        font: ui_resources.font.clone(),
+                        font_size: 20.,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(0., 0., 2.),
+                ..default()
+            });
+        });
+}
+
+fn update_lasers(
+    mut commands: Commands,
+    emitters: Query<(&Emitter, &GridPos, &Children)>,
+    mut beams: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<LaserBeam>>,
+    mut receivers: Query<(&mut Receiver, &GridPos, &Handle<ColorMaterial>)>,
+    snakes: Query<(Entity, &GridPos, Has<CanMove>, Has<Move>), With<SnakeSize>>,
+    enemies: Query<(Entity, &GridPos), With<Enemy>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    map: Res<GameMap>,
+) {
+    let mut dead = Vec::new();
+
+    for (Emitter(dir), emitter_pos, children) in emitters.iter() {
+        let mut path = Vec::new();
+        let mut pos = emitter_pos.0;
+
+        // the beam goes until it hits something solid, body pieces included
+        let blocker = loop {
+            let Some(next) = usize::try_from(pos[0] as isize + dir.x as isize)
+                .ok()
+                .zip(usize::try_from(pos[1] as isize + dir.y as isize).ok())
+                .map(|(x, y)| [x, y])
+            else {
+                break None;
+            };
+
+            match map.get(next) {
+                None => break None,
+                Some(tile) if tile.is_occupied() => break Some(GridPos(next)),
+                Some(_) => {
+                    path.push(GridPos(next));
+                    pos = next;
+                }
+            }
+        };
+
+        // a head stops the beam but doesn't survive it
+        dead.extend(snakes.iter().filter_map(|(ent, pos, can_move, moving)| {
+            (path.contains(pos) || (Some(*pos) == blocker && (can_move || moving))).then_some(ent)
+        }));
+        dead.extend(
+            enemies
+                .iter()
+                .filter_map(|(ent, pos)| path.contains(pos).then_some(ent)),
+        );
+
+        if let Some((mut receiver, _, material)) = receivers
+            .iter_mut()
+            .find(|(receiver, pos, _)| !receiver.powered && Some(**pos) == blocker)
+        {
+            receiver.powered = true;
+            if let Some(material) = materials.get_mut(material.id()) {
+                material.color = Color::srgb(0.5, 0.5, 4.);
+            }
+            commands.trigger(ChannelActivated(receiver.channel));
+        }
+
+        let mut beams = beams.iter_many_mut(children);
+        while let Some((mut sprite, mut transform, mut visibility)) = beams.fetch_next() {
+            let length = path.len() as f32 * GRID_CELL_SIZE.x;
+
+            *visibility = if path.is_empty() {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+            sprite.custom_size = Some(if dir.x != 0 {
+                Vec2::new(length, BEAM_WIDTH)
+            } else {
+                Vec2::new(BEAM_WIDTH, length)
+            });
+            transform.translation =
+                (dir.as_vec2() * (GRID_CELL_SIZE.x + length) / 2.).extend(transform.translation.z);
+        }
+    }
+
+    dead.sort();
+    dead.dedup();
+    for ent in dead {
+        commands.trigger_targets(PieceKilled(DeathCause::Laser), ent);
+        commands.trigger(SoundEvent::Spike);
+    }
+}
@@ -1,38 +1,71 @@
-#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+#![allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    clippy::manual_repeat_n,
+    clippy::unnecessary_sort_by
+)]
 
 use bevy::{
     asset::AssetMetaCheck,
-    core_pipeline::{
-        bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings},
-        tonemapping::Tonemapping,
-    },
+    core_pipeline::tonemapping::Tonemapping,
     log::{Level, LogPlugin},
     prelude::*,
 };
 
+use console::console_plugin;
+use crumble::crumble_plugin;
 use door::door_plugin;
+use enemy::enemy_plugin;
 use fade_out::fade_out_plugin;
 use game_over::game_over_plugin;
+use gravity::gravity_plugin;
 use input::input_plugin;
+use keymap::keymap_plugin;
+use keys::keys_plugin;
+use laser::laser_plugin;
 use main_menu::main_menu_ui_plugin;
 use map::map_plugin;
+use objectives::objectives_plugin;
+use pause::{pause_plugin, PauseState};
+use pellet::pellet_plugin;
+use pointer::pointer_plugin;
+use rewind::rewind_plugin;
+use save::save_plugin;
+use settings::settings_plugin;
 use snake::snake_plugin;
 use sounds::sounds_plugin;
 use spike::spike_plugin;
 use title::title_ui_plugin;
+use turn::turn_plugin;
 use ui::game_ui_plugin;
 use wall::wall_plugin;
 
+mod console;
+mod crumble;
 mod door;
+mod enemy;
 mod fade_out;
 mod game_over;
+mod gravity;
+mod ice;
 mod input;
+mod keymap;
+mod keys;
+mod laser;
 mod main_menu;
 mod map;
+mod objectives;
+mod pause;
+mod pellet;
+mod pointer;
+mod rewind;
+mod save;
+mod settings;
 mod snake;
 mod sounds;
 mod spike;
 mod title;
+mod turn;
 mod ui;
 mod wall;
 
@@ -94,16 +127,33 @@ fn main() {
             sounds_plugin,
             fade_out_plugin,
         ))
+        .add_plugins((
+            turn_plugin,
+            pellet_plugin,
+            objectives_plugin,
+            crumble_plugin,
+            enemy_plugin,
+            laser_plugin,
+            keys_plugin,
+            gravity_plugin,
+            keymap_plugin,
+            pointer_plugin,
+            rewind_plugin,
+            console_plugin,
+            pause_plugin,
+            settings_plugin,
+            save_plugin,
+        ))
         .enable_state_scoped_entities::<GameState>()
         .configure_sets(
             Update,
             (
-                GameplaySet::Input.run_if(in_state(GameState::Gaming)),
+                GameplaySet::Input.run_if(in_state(PauseState::Running)),
                 GameplaySet::Behavior
-                    .run_if(in_state(GameState::Gaming))
+                    .run_if(in_state(PauseState::Running))
                     .after(GameplaySet::Input),
                 GameplaySet::After
-                    .run_if(in_state(GameState::Gaming))
+                    .run_if(in_state(PauseState::Running))
                     .after(GameplaySet::Behavior),
             ),
         )
@@ -114,25 +164,13 @@ fn main() {
 }
 
 fn main_setup(mut commands: Commands) {
-    commands.spawn((
-        Camera2dBundle {
-            camera: Camera {
-                hdr: true,
-                ..default()
-            },
-            tonemapping: Tonemapping::TonyMcMapface,
+    // bloom is added by the settings
+    commands.spawn(Camera2dBundle {
+        camera: Camera {
+            hdr: true,
             ..default()
         },
-        BloomSettings {
-            intensity: 0.20,
-            low_frequency_boost: 0.7,
-            low_frequency_boost_curvature: 0.95,
-            high_pass_frequency: 1.0,
-            prefilter_settings: BloomPrefilterSettings {
-                threshold: 0.0,
-                threshold_softness: 0.0,
-            },
-            composite_mode: BloomCompositeMode::EnergyConserving,
-        },
-    ));
+        tonemapping: Tonemapping::TonyMcMapface,
+        ..default()
+    });
 }
@@ -1,7 +1,17 @@
-use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    ui::{FocusPolicy, UiSystem},
+    utils::HashSet,
+};
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const PRESSED_BUTTON: Color = Color::srgb(0.30, 0.30, 0.30);
+pub const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+pub const PRESSED_BUTTON: Color = Color::srgb(0.30, 0.30, 0.30);
+const FOCUSED_BUTTON: Color = Color::srgb(0.85, 0.80, 0.45);
+
+// buttons built by `create_button`, the ones keyboard and gamepad focus moves between
+#[derive(Debug, Component)]
+pub struct MenuButton;
 
 #[derive(Debug, Component)]
 struct GamingButton;
@@ -24,6 +34,76 @@ struct ToLevelSeLectionButton;
 #[derive(Debug, Component)]
 struct SelectLevelButton(usize);
 
+// where the beaten levels lead, filled in from the progress
+#[derive(Debug, Component)]
+struct BranchMapText;
+
+// a level none of the beaten ones lead to yet
+#[derive(Debug, Component)]
+struct LockedLevel;
+
+// picks up at the first level not beaten yet, hidden when there's nothing to pick up
+#[derive(Debug, Component)]
+struct ContinueButton;
+
+#[derive(Debug, Component)]
+struct TurnModeButton;
+
+#[derive(Debug, Component)]
+struct ControlsButton;
+
+#[derive(Debug, Component)]
+struct SettingsButton;
+
+#[derive(Debug, Component)]
+struct RebindButton(Action);
+
+#[derive(Debug, Component)]
+struct PresetButton(Preset);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    MasterVolume,
+    EffectsVolume,
+    Bloom,
+    BloomIntensity,
+    Fullscreen,
+    WindowSize,
+    AnimationSpeed,
+    Colourblind,
+    RepeatDelay,
+    RepeatInterval,
+}
+
+impl Setting {
+    const ALL: [Setting; 10] = [
+        Setting::MasterVolume,
+        Setting::EffectsVolume,
+        Setting::Bloom,
+        Setting::BloomIntensity,
+        Setting::Fullscreen,
+        Setting::WindowSize,
+        Setting::AnimationSpeed,
+        Setting::Colourblind,
+        Setting::RepeatDelay,
+        Setting::RepeatInterval,
+    ];
+}
+
+#[derive(Debug, Component)]
+struct SettingButton(Setting);
+
+// the action waiting for its new key
+#[derive(Debug, Resource, Default)]
+struct Rebinding(Option<Action>);
+
+// the button keyboard and gamepad input goes to
+#[derive(Debug, Resource, Default)]
+struct MenuFocus {
+    focused: Option<Entity>,
+    activated: Option<Entity>,
+}
+
 #[derive(Debug, SubStates, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 #[source(GameState = GameState::MainMenu)]
 pub enum MainMenuState {
@@ -34,17 +114,32 @@ pub enum MainMenuState {
     ToGaming,
     Quit,
     LevelSelection,
+    Controls,
+    Settings,
 }
 
-use crate::{map::MapName, sounds::SoundEvent, title::UiResources, GameState};
+use crate::{
+    input::{GamepadInput, KeyRepeat},
+    keymap::{Action, Binding, Keymap, Preset, BINDABLE, MODIFIERS},
+    map::{level_label, level_map, level_number, levels, MapName},
+    objectives::LevelProgress,
+    pause::is_paused,
+    settings::{
+        Settings, ANIMATION_SPEED_OPTIONS, BLOOM_INTENSITY_OPTIONS, REPEAT_DELAY_OPTIONS,
+        REPEAT_INTERVAL_OPTIONS, VOLUME_OPTIONS, WINDOW_WIDTH_OPTIONS,
+    },
+    snake::AnimationSpeed,
+    sounds::SoundEvent,
+    title::UiResources,
+    turn::{PreferredTurnMode, TurnMode},
+    GameState,
+};
 
 pub fn main_menu_ui_plugin(app: &mut App) {
     app.add_sub_state::<MainMenuState>()
         .enable_state_scoped_entities::<MainMenuState>()
-        .add_systems(
-            OnEnter(GameState::MainMenu),
-            setup_menu_builder(setup_main_ui),
-        )
+        .init_resource::<Rebinding>()
+        .init_resource::<MenuFocus>()
         .add_systems(
             OnEnter(MainMenuState::Main),
             setup_menu_builder(setup_main_ui),
@@ -61,6 +156,18 @@ pub fn main_menu_ui_plugin(app: &mut App) {
             OnEnter(MainMenuState::LevelSelection),
             setup_menu_builder(setup_level_selection),
         )
+        .add_systems(
+            OnEnter(MainMenuState::Controls),
+            setup_menu_builder(setup_controls_ui),
+        )
+        .add_systems(
+            OnEnter(MainMenuState::Settings),
+            setup_menu_builder(setup_settings_ui),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Controls),
+            |mut rebinding: ResMut<Rebinding>| rebinding.0 = None,
+        )
         .add_systems(
             OnEnter(MainMenuState::ToGaming),
             |mut next_state: ResMut<NextState<GameState>>| next_state.set(GameState::Loading),
@@ -82,16 +189,171 @@ pub fn main_menu_ui_plugin(app: &mut App) {
                 transition_to_builder::<GoBackButton>(MainMenuState::Main),
                 transition_to_builder::<QuitButton>(MainMenuState::Quit),
                 transition_to_builder::<ToLevelSeLectionButton>(MainMenuState::LevelSelection),
-                skip_to_play,
+                transition_to_builder::<ControlsButton>(MainMenuState::Controls),
+                transition_to_builder::<SettingsButton>(MainMenuState::Settings),
                 select_level_button,
+                continue_button,
+                hide_continue,
+                toggle_turn_mode_button,
+                update_turn_mode_text,
+                show_level_progress,
+                show_branch_map,
+                (
+                    rebind_button,
+                    preset_button,
+                    capture_rebind,
+                    update_rebind_text,
+                )
+                    .chain(),
             )
                 .run_if(in_state(GameState::MainMenu)),
+        )
+        .add_systems(
+            PreUpdate,
+            navigate_focus
+                .after(UiSystem::Focus)
+                .run_if(in_state(GameState::MainMenu).or_else(is_paused)),
+        )
+        .add_systems(Update, (cycle_setting_button, update_setting_text).chain())
+        .add_systems(
+            PostUpdate,
+            show_focus.run_if(in_state(GameState::MainMenu).or_else(is_paused)),
         );
 }
 
-fn skip_to_play(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<MainMenuState>>) {
-    if keys.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+// keyboard and gamepad presses go through the same interaction the mouse uses
+fn navigate_focus(
+    keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    gamepad: GamepadInput,
+    rebinding: Res<Rebinding>,
+    mut focus: ResMut<MenuFocus>,
+    menu_state: Option<Res<State<MainMenuState>>>,
+    mut next_state: ResMut<NextState<MainMenuState>>,
+    mut last_stick: Local<Option<IVec2>>,
+    mut buttons: Query<(Entity, &mut Interaction, &Node, &GlobalTransform), With<MenuButton>>,
+) {
+    if let Some((_, mut interaction, ..)) = focus
+        .activated
+        .take()
+        .and_then(|ent| buttons.get_mut(ent).ok())
+    {
+        if *interaction == Interaction::Pressed {
+            *interaction = Interaction::None;
+        }
+    }
+
+    if rebinding.0.is_some() {
+        return;
+    }
+
+    if let Some((ent, ..)) = buttons.iter_mut().find(|(_, interaction, ..)| {
+        interaction.is_changed() && **interaction == Interaction::Hovered
+    }) {
+        focus.focused = Some(ent);
+    }
+
+    // reading order, buttons without a layout yet are left out
+    let mut positions = buttons
+        .iter()
+        .filter(|(_, _, node, _)| node.size() != Vec2::ZERO)
+        .map(|(ent, _, _, transform)| (ent, transform.translation().truncate()))
+        .collect::<Vec<_>>();
+    positions.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let focused_index = focus
+        .focused
+        .and_then(|focused| positions.iter().position(|(ent, _)| *ent == focused));
+
+    // until something has focus enter and space on the title screen go straight to playing
+    let on_title = menu_state.is_some_and(|state| *state.get() == MainMenuState::Main);
+    if on_title
+        && focused_index.is_none()
+        && keys.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+    {
         next_state.set(MainMenuState::ToGaming);
+        return;
+    }
+
+    let Some(mut index) = focused_index.or((!positions.is_empty()).then_some(0)) else {
+        return;
+    };
+
+    let stick = gamepad.direction();
+    let stick_moved = stick.filter(|_| stick != *last_stick);
+    *last_stick = stick;
+
+    let dir = [
+        (Action::MoveUp, KeyCode::ArrowUp, IVec2::Y),
+        (Action::MoveDown, KeyCode::ArrowDown, IVec2::NEG_Y),
+        (Action::MoveLeft, KeyCode::ArrowLeft, IVec2::NEG_X),
+        (Action::MoveRight, KeyCode::ArrowRight, IVec2::X),
+    ]
+    .into_iter()
+    .find(|(action, key, _)| keymap.just_pressed(*action, &keys) || keys.just_pressed(*key))
+    .map(|(_, _, dir)| dir)
+    .or(stick_moved);
+
+    if let Some(dir) = dir {
+        // ui space grows downwards
+        let dir = Vec2::new(dir.x as f32, -dir.y as f32);
+        let from = positions[index].1;
+
+        if let Some((closest, _)) = positions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, pos))| {
+                let offset = *pos - from;
+                let along = offset.dot(dir);
+                (along > 0.).then_some((i, along + offset.perp_dot(dir).abs() * 2.))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        {
+            index = closest;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Tab) {
+        index = if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            (index + positions.len() - 1) % positions.len()
+        } else {
+            (index + 1) % positions.len()
+        };
+    }
+
+    // the title screen only shows focus once it's been moved there
+    let focused = positions[index].0;
+    if focused_index.is_some() || dir.is_some() || keys.just_pressed(KeyCode::Tab) || !on_title {
+        focus.focused = Some(focused);
+    }
+
+    if keymap.just_pressed(Action::Confirm, &keys)
+        || keys.just_pressed(KeyCode::Space)
+        || gamepad.just_pressed(GamepadButtonType::South)
+        || gamepad.just_pressed(GamepadButtonType::Start)
+    {
+        if let Ok((_, mut interaction, ..)) = buttons.get_mut(focused) {
+            *interaction = Interaction::Pressed;
+            focus.activated = Some(focused);
+        }
+    }
+}
+
+fn show_focus(
+    focus: Res<MenuFocus>,
+    mut buttons: Query<(Entity, &mut BorderColor, &Interaction), With<MenuButton>>,
+) {
+    for (ent, mut border_color, interaction) in buttons.iter_mut() {
+        let color = match *interaction {
+            Interaction::Pressed => continue,
+            _ if focus.focused == Some(ent) => FOCUSED_BUTTON,
+            Interaction::Hovered => PRESSED_BUTTON,
+            Interaction::None => NORMAL_BUTTON,
+        };
+
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
     }
 }
 
@@ -117,6 +379,8 @@ fn setup_main_ui(parent: &mut ChildBuilder, _ui_resources: &UiResources, button_
         .insert(StateScoped(MainMenuState::Main))
         .insert(StateScoped(GameState::MainMenu));
 
+    create_button(parent, "Continue", button_style.clone(), ContinueButton)
+        .insert(StateScoped(MainMenuState::Main));
     create_button(parent, "Play", button_style.clone(), GamingButton)
         .insert(StateScoped(MainMenuState::Main));
     create_button(
@@ -126,6 +390,12 @@ fn setup_main_ui(parent: &mut ChildBuilder, _ui_resources: &UiResources, button_
         ToLevelSeLectionButton,
     )
     .insert(StateScoped(MainMenuState::Main));
+    create_button(parent, "Real Time", button_style.clone(), TurnModeButton)
+        .insert(StateScoped(MainMenuState::Main));
+    create_button(parent, "Controls", button_style.clone(), ControlsButton)
+        .insert(StateScoped(MainMenuState::Main));
+    create_button(parent, "Settings", button_style.clone(), SettingsButton)
+        .insert(StateScoped(MainMenuState::Main));
     create_button(parent, "Credits", button_style.clone(), CreditsButton)
         .insert(StateScoped(MainMenuState::Main));
     create_button(parent, "Cat", button_style.clone(), CatButton)
@@ -245,11 +515,18 @@ fn setup_level_selection(
             ..default()
         })
         .with_children(|parent| {
-            // TODO: count levels
-            (1..=16).for_each(|i| {
+            let count = levels().len();
+            (1..=count).for_each(|i| {
                 _ = create_button(
                     parent,
-                    &format!("level {}", if i == 16 { "X".into() } else { i.to_string() }),
+                    &format!(
+                        "level {}",
+                        if i == count {
+                            "X".into()
+                        } else {
+                            i.to_string()
+                        }
+                    ),
                     button_style.clone(),
                     SelectLevelButton(i),
                 )
@@ -257,9 +534,138 @@ fn setup_level_selection(
         })
         .insert(StateScoped(MainMenuState::LevelSelection));
 
+    parent
+        .spawn(TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 15.,
+                ..button_style.clone()
+            },
+        ))
+        .insert((BranchMapText, StateScoped(MainMenuState::LevelSelection)));
+
     create_button(parent, "Go Back", button_style, GoBackButton)
         .insert(StateScoped(MainMenuState::LevelSelection));
 }
+
+fn setup_controls_ui(
+    parent: &mut ChildBuilder,
+    _ui_resources: &UiResources,
+    button_style: TextStyle,
+) {
+    let small_style = TextStyle {
+        font_size: 15.,
+        ..button_style.clone()
+    };
+
+    parent
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "CONTROLS",
+                TextStyle {
+                    font_size: 50.,
+                    ..button_style.clone()
+                },
+            ),
+            ..default()
+        })
+        .insert(StateScoped(MainMenuState::Controls));
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(150.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                justify_self: JustifySelf::Center,
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::flex(4, 1.0),
+                column_gap: Val::Px(30.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for action in Action::ALL {
+                _ = create_button(
+                    parent,
+                    action.label(),
+                    small_style.clone(),
+                    RebindButton(action),
+                );
+            }
+            for preset in [Preset::Arrows, Preset::Wasd, Preset::Hjkl] {
+                _ = create_button(
+                    parent,
+                    preset.label(),
+                    button_style.clone(),
+                    PresetButton(preset),
+                );
+            }
+        })
+        .insert(StateScoped(MainMenuState::Controls));
+
+    create_button(parent, "Go Back", button_style, GoBackButton)
+        .insert(StateScoped(MainMenuState::Controls));
+}
+
+fn setup_settings_ui(
+    parent: &mut ChildBuilder,
+    _ui_resources: &UiResources,
+    button_style: TextStyle,
+) {
+    parent
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "SETTINGS",
+                TextStyle {
+                    font_size: 50.,
+                    ..button_style.clone()
+                },
+            ),
+            ..default()
+        })
+        .insert(StateScoped(MainMenuState::Settings));
+
+    spawn_setting_buttons(
+        parent,
+        TextStyle {
+            font_size: 15.,
+            ..button_style.clone()
+        },
+    )
+    .insert(StateScoped(MainMenuState::Settings));
+
+    create_button(parent, "Go Back", button_style, GoBackButton)
+        .insert(StateScoped(MainMenuState::Settings));
+}
+
+// shared with the pause menu, so the grid is returned for the caller to scope
+pub fn spawn_setting_buttons<'a>(
+    parent: &'a mut ChildBuilder,
+    style: TextStyle,
+) -> EntityCommands<'a> {
+    let mut grid = parent.spawn(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            justify_self: JustifySelf::Center,
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::flex(5, 1.0),
+            column_gap: Val::Px(30.0),
+            ..default()
+        },
+        ..default()
+    });
+
+    grid.with_children(|parent| {
+        for setting in Setting::ALL {
+            _ = create_button(parent, "", style.clone(), SettingButton(setting));
+        }
+    });
+    grid
+}
+
 fn setup_menu(
     mut commands: Commands,
     ui_resources: Res<UiResources>,
@@ -296,7 +702,7 @@ fn setup_menu_builder<T: Fn(&mut ChildBuilder, &UiResources, TextStyle) + Clone>
     }
 }
 
-fn create_button<'a, T: Component>(
+pub fn create_button<'a, T: Component>(
     parent: &'a mut ChildBuilder,
     text: &str,
     style: TextStyle,
@@ -321,6 +727,7 @@ fn create_button<'a, T: Component>(
             ..default()
         },
         tag,
+        MenuButton,
         StateScoped(GameState::MainMenu),
     ));
 
@@ -344,7 +751,7 @@ fn select_level_button(
             &Interaction,
             &SelectLevelButton,
         ),
-        (Changed<Interaction>, With<Button>),
+        (Changed<Interaction>, With<Button>, Without<LockedLevel>),
     >,
     mut next_state: ResMut<NextState<MainMenuState>>,
     mut map_name: ResMut<MapName>,
@@ -355,8 +762,443 @@ fn select_level_button(
                 *color = PRESSED_BUTTON.into();
                 border_color.0 = Color::BLACK;
 
-                map_name.0 = format!("maps/map_{}.game_map", level.0);
-                next_state.set(MainMenuState::ToGaming);
+                if let Some(path) = level_map(level.0) {
+                    map_name.0 = path;
+                    next_state.set(MainMenuState::ToGaming);
+                }
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
+
+fn toggle_turn_mode_button(
+    mut commands: Commands,
+    mut button: Query<
+        (&mut BorderColor, &mut BackgroundColor, &Interaction),
+        (Changed<Interaction>, With<Button>, With<TurnModeButton>),
+    >,
+    mut preferred: ResMut<PreferredTurnMode>,
+) {
+    for (mut border_color, mut color, interaction) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                preferred.0 = match preferred.0 {
+                    TurnMode::RealTime => TurnMode::TurnBased,
+                    TurnMode::TurnBased => TurnMode::RealTime,
+                };
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
+
+fn update_turn_mode_text(
+    preferred: Res<PreferredTurnMode>,
+    buttons: Query<(&Children, Ref<TurnModeButton>)>,
+    mut texts: Query<&mut Text>,
+) {
+    for children in buttons
+        .iter()
+        .filter(|(_, button)| button.is_added() || preferred.is_changed())
+        .map(|(children, _)| children)
+    {
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = match preferred.0 {
+                TurnMode::RealTime => "Real Time",
+                TurnMode::TurnBased => "Turn Based",
+            }
+            .to_string();
+        }
+    }
+}
+
+fn rebind_button(
+    mut commands: Commands,
+    mut button: Query<
+        (
+            &mut BorderColor,
+            &mut BackgroundColor,
+            &Interaction,
+            &RebindButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (mut border_color, mut color, interaction, button) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                rebinding.0 = Some(button.0);
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
+
+fn preset_button(
+    mut commands: Commands,
+    mut button: Query<
+        (
+            &mut BorderColor,
+            &mut BackgroundColor,
+            &Interaction,
+            &PresetButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut keymap: ResMut<Keymap>,
+) {
+    for (mut border_color, mut color, interaction, button) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                keymap.apply_preset(button.0);
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
+
+// modifiers held with the key become part of the binding
+fn capture_rebind(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut keymap: ResMut<Keymap>,
+) {
+    // the key that started the rebind doesn't count
+    let Some(action) = rebinding.0.filter(|_| !rebinding.is_changed()) else {
+        return;
+    };
+
+    // only keys the keymap file can store, anything else would be lost on the next load
+    let Some(key) = keys
+        .get_just_pressed()
+        .find(|key| BINDABLE.contains(key) && !MODIFIERS.contains(key))
+        .copied()
+    else {
+        return;
+    };
+
+    let mut chord = MODIFIERS
+        .into_iter()
+        .filter(|modifier| keys.pressed(*modifier))
+        .collect::<Vec<_>>();
+    chord.push(key);
+
+    keymap.0.insert(action, Binding(chord));
+    rebinding.0 = None;
+}
+
+fn update_rebind_text(
+    keymap: Res<Keymap>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&Children, Ref<RebindButton>)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (children, button) in buttons
+        .iter()
+        .filter(|(_, button)| button.is_added() || keymap.is_changed() || rebinding.is_changed())
+    {
+        let binding = if rebinding.0 == Some(button.0) {
+            "press a key".to_string()
+        } else {
+            keymap
+                .0
+                .get(&button.0)
+                .map(Binding::describe)
+                .unwrap_or_default()
+        };
+
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = format!("{}\n{binding}", button.0.label());
+        }
+    }
+}
+
+fn cycle_setting_button(
+    mut commands: Commands,
+    mut button: Query<
+        (
+            &mut BorderColor,
+            &mut BackgroundColor,
+            &Interaction,
+            &SettingButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut repeat: ResMut<KeyRepeat>,
+    mut speed: ResMut<AnimationSpeed>,
+    mut settings: ResMut<Settings>,
+) {
+    // steps to the next of the values a press cycles through
+    fn cycle(value: &mut f32, options: &[f32]) {
+        *value = options
+            .iter()
+            .position(|option| option == value)
+            .map_or(options[0], |index| options[(index + 1) % options.len()]);
+    }
+
+    for (mut border_color, mut color, interaction, button) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                match button.0 {
+                    Setting::MasterVolume => cycle(&mut settings.master_volume, &VOLUME_OPTIONS),
+                    Setting::EffectsVolume => cycle(&mut settings.effects_volume, &VOLUME_OPTIONS),
+                    Setting::Bloom => settings.bloom = !settings.bloom,
+                    Setting::BloomIntensity => {
+                        cycle(&mut settings.bloom_intensity, &BLOOM_INTENSITY_OPTIONS)
+                    }
+                    Setting::Fullscreen => settings.fullscreen = !settings.fullscreen,
+                    Setting::WindowSize => cycle(&mut settings.window_width, &WINDOW_WIDTH_OPTIONS),
+                    Setting::AnimationSpeed => cycle(&mut speed.0, &ANIMATION_SPEED_OPTIONS),
+                    Setting::Colourblind => settings.colourblind = !settings.colourblind,
+                    Setting::RepeatDelay => cycle(&mut repeat.delay, &REPEAT_DELAY_OPTIONS),
+                    Setting::RepeatInterval => {
+                        cycle(&mut repeat.interval, &REPEAT_INTERVAL_OPTIONS)
+                    }
+                }
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
+
+fn update_setting_text(
+    repeat: Res<KeyRepeat>,
+    speed: Res<AnimationSpeed>,
+    settings: Res<Settings>,
+    buttons: Query<(&Children, Ref<SettingButton>)>,
+    mut texts: Query<&mut Text>,
+) {
+    let on_off = |on| if on { "on" } else { "off" };
+    let percent = |volume: f32| format!("{}%", (volume * 100.).round());
+
+    for (children, button) in buttons.iter().filter(|(_, button)| {
+        button.is_added() || repeat.is_changed() || speed.is_changed() || settings.is_changed()
+    }) {
+        let label = match button.0 {
+            Setting::MasterVolume => format!("Master Volume\n{}", percent(settings.master_volume)),
+            Setting::EffectsVolume => {
+                format!("Effects Volume\n{}", percent(settings.effects_volume))
+            }
+            Setting::Bloom => format!("Bloom\n{}", on_off(settings.bloom)),
+            Setting::BloomIntensity => format!("Bloom Strength\n{}", settings.bloom_intensity),
+            Setting::Fullscreen => format!("Fullscreen\n{}", on_off(settings.fullscreen)),
+            Setting::WindowSize => format!(
+                "Window Size\n{}x{}",
+                settings.window_width,
+                settings.window_height()
+            ),
+            Setting::AnimationSpeed => format!("Animation Speed\n{}x", speed.0),
+            Setting::Colourblind => format!("Colourblind\n{}", on_off(settings.colourblind)),
+            Setting::RepeatDelay => format!("Repeat Delay\n{}s", repeat.delay),
+            Setting::RepeatInterval => format!("Repeat Every\n{}s", repeat.interval),
+        };
+
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&label);
+        }
+    }
+}
+
+fn show_level_progress(
+    mut commands: Commands,
+    progress: Res<LevelProgress>,
+    buttons: Query<(Entity, &Children, &SelectLevelButton), Added<SelectLevelButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children, level) in buttons.iter() {
+        let summary = if !progress.unlocked(level.0) {
+            commands.entity(button).insert(LockedLevel);
+            vec!["locked".to_string()]
+        } else if let Some(record) = level_map(level.0).and_then(|path| progress.0.get(&path)) {
+            let mut summary = vec!["done".to_string()];
+            if let Some(moves) = record.best_moves {
+                summary.push(format!("{moves} moves"));
+            }
+            if let Some(rewinds) = record.fewest_rewinds {
+                summary.push(format!("{rewinds} rewinds"));
+            }
+            if record.stars_total != 0 {
+                summary.push(format!("{}/{} stars", record.stars, record.stars_total));
+            }
+            if record.par_met {
+                summary.push("par".to_string());
+            }
+            summary
+        } else {
+            continue;
+        };
+
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            let style = TextStyle {
+                font_size: 15.,
+                ..text.sections[0].style.clone()
+            };
+            text.sections.push(TextSection::new(
+                format!("\n{}", summary.join(" | ")),
+                style,
+            ));
+        }
+    }
+}
+
+fn show_branch_map(
+    progress: Res<LevelProgress>,
+    mut texts: Query<&mut Text, Added<BranchMapText>>,
+) {
+    for mut text in texts.iter_mut() {
+        let mut lines = vec!["ROUTES".to_string()];
+        if let Some(first) = level_map(1) {
+            push_route(&progress, first, 0, &mut HashSet::new(), &mut lines);
+        }
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+// where a map leads, the recorded branches or else the next level in the list once beaten
+fn route_next(progress: &LevelProgress, map: &str) -> Vec<String> {
+    let Some(record) = progress.0.get(map) else {
+        return Vec::new();
+    };
+
+    if !record.branches.is_empty() {
+        return record.branches.clone();
+    }
+
+    level_number(map)
+        .and_then(|level| level_map(level + 1))
+        .into_iter()
+        .collect()
+}
+
+// one line per stretch without choices, a level with several ways out starts indented lines
+fn push_route(
+    progress: &LevelProgress,
+    from: String,
+    depth: usize,
+    seen: &mut HashSet<String>,
+    lines: &mut Vec<String>,
+) {
+    let mut route = vec![from];
+    let branches = loop {
+        let last = route.last().expect("starts with one").clone();
+        seen.insert(last.clone());
+
+        let mut next = route_next(progress, &last)
+            .into_iter()
+            .filter(|map| !seen.contains(map))
+            .collect::<Vec<_>>();
+        if next.len() != 1 {
+            break next;
+        }
+        route.push(next.remove(0));
+    };
+
+    let label = |map: &String| {
+        let name = level_label(map);
+        if progress.0.contains_key(map) {
+            name
+        } else {
+            format!("{name} (new)")
+        }
+    };
+
+    lines.push(format!(
+        "{}{}",
+        "    ".repeat(depth),
+        route.iter().map(label).collect::<Vec<_>>().join(" > ")
+    ));
+
+    for branch in branches {
+        if !seen.contains(&branch) {
+            push_route(progress, branch, depth + 1, seen, lines);
+        }
+    }
+}
+
+fn hide_continue(
+    progress: Res<LevelProgress>,
+    mut buttons: Query<&mut Style, Added<ContinueButton>>,
+) {
+    for mut style in buttons.iter_mut() {
+        if progress.0.is_empty() || progress.first_unbeaten().is_none() {
+            style.display = Display::None;
+        }
+    }
+}
+
+fn continue_button(
+    mut commands: Commands,
+    mut button: Query<
+        (&mut BorderColor, &mut BackgroundColor, &Interaction),
+        (Changed<Interaction>, With<Button>, With<ContinueButton>),
+    >,
+    progress: Res<LevelProgress>,
+    mut next_state: ResMut<NextState<MainMenuState>>,
+    mut map_name: ResMut<MapName>,
+) {
+    for (mut border_color, mut color, interaction) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                if let Some(path) = progress.first_unbeaten().and_then(level_map) {
+                    map_name.0 = path;
+                    next_state.set(MainMenuState::ToGaming);
+                }
                 commands.trigger(SoundEvent::Select);
             }
             Interaction::Hovered => {
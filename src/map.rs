@@ -1,4 +1,4 @@
-use std::iter;
+use std::{cmp::Reverse, iter};
 
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
@@ -7,12 +7,23 @@ use bevy::{
 };
 
 use crate::{
+    console::{Console, ConsoleApp, ConsoleArgs},
+    crumble::{spawn_crumbling_floor, spawn_pit, FloorStepped},
     door::{
-        spawn_anti_door, spawn_door, spawn_pressure_plate, DoorSprites, PressurePlateActivated,
+        spawn_anti_door, spawn_door, spawn_pressure_plate, spawn_switch, spawn_timed_door,
+        DoorSprites, DoorTimer, PressurePlateActivated, SwitchToggled,
+    },
+    enemy::parse_patrol,
+    game_over::{spawn_exit, ExitCondition, ExitReached, WinCondition},
+    gravity::parse_gravity,
+    ice::spawn_ice,
+    keys::{spawn_key, spawn_locked_door, KeyPickedUp},
+    laser::{spawn_emitter, spawn_receiver},
+    objectives::{spawn_star, StarCollected},
+    pellet::{spawn_pellet, Pellet, PelletEaten},
+    snake::{
+        snake_color, spawn_snake_piece, CanMove, RewindCounter, SnakeId, SnakeIndex, SnakeSize,
     },
-    game_over::{spawn_exit, GameWinTrigger},
-    snake::{spawn_snake_piece, CanMove, RewindCounter, SnakeIndex, SnakeSize},
-    sounds::SoundEvent,
     spike::{spawn_spike, SpikeSprites},
     title::UiResources,
     wall::spawn_wall,
@@ -24,6 +35,10 @@ pub const GRID_CELL_SIZE: Vec2 = Vec2::new(50., 50.);
 #[derive(Debug, Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct GridPos(pub [usize; 2]);
 
+// items lying on the bottom layer, removing them leaves whatever is on top alone
+#[derive(Debug, Component)]
+pub struct Pickup;
+
 impl GridPos {
     pub fn to_vec2(self) -> Vec2 {
         Vec2::from_array(self.0.map(|i| i as f32))
@@ -45,9 +60,14 @@ pub struct Tile {
 
 #[derive(Debug, Default, Clone, Copy)]
 pub enum TopTileType {
-    Snake,
+    Snake(SnakeId),
     Wall,
     Door(char),
+    Pit,
+    Emitter(IVec2),
+    Receiver(char),
+    Locked(char),
+    TimedDoor(char),
     #[default]
     Nothing,
 }
@@ -59,6 +79,12 @@ pub enum BottomTileType {
     Spike,
     TextHint(Box<str>),
     AntiDoor(char),
+    Pellet(Pellet),
+    Star,
+    Crumbling,
+    Key(char),
+    Switch(char),
+    Ice,
     #[default]
     Nothing,
 }
@@ -75,21 +101,107 @@ impl Tile {
         !matches!(self.top, TopTileType::Nothing)
     }
 
-    pub fn snake_passes(&mut self, pos: [usize; 2], commands: &mut Commands) {
-        self.top = TopTileType::Snake;
+    pub fn blocks_enemies(&self) -> bool {
+        matches!(
+            self.top,
+            TopTileType::Wall
+                | TopTileType::Door(_)
+                | TopTileType::Pit
+                | TopTileType::Emitter(_)
+                | TopTileType::Receiver(_)
+                | TopTileType::Locked(_)
+                | TopTileType::TimedDoor(_)
+        )
+    }
+
+    pub fn is_pit(&self) -> bool {
+        matches!(self.top, TopTileType::Pit)
+    }
+
+    // whether a snake resting on this tile is held up against gravity
+    pub fn supports(&self, snake: SnakeId) -> bool {
+        match self.top {
+            TopTileType::Snake(other) => other != snake,
+            TopTileType::Pit => false,
+            TopTileType::Nothing => matches!(
+                self.bottom,
+                BottomTileType::PressurePlate(_) | BottomTileType::Switch(_)
+            ),
+            _ => true,
+        }
+    }
+
+    pub fn hold_snake(&mut self, snake: SnakeId) {
+        self.top = TopTileType::Snake(snake);
+    }
+
+    pub fn is_slippery(&self) -> bool {
+        matches!(self.bottom, BottomTileType::Ice)
+    }
+
+    pub fn locked_with(&self) -> Option<char> {
+        match self.top {
+            TopTileType::Locked(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    pub fn set_bottom(&mut self, bottom: BottomTileType) {
+        self.bottom = bottom;
+    }
+
+    pub fn snake_passes(&mut self, pos: [usize; 2], snake: SnakeId, commands: &mut Commands) {
+        self.top = TopTileType::Snake(snake);
 
         if let BottomTileType::PressurePlate(channel) = self.bottom {
             commands.trigger(PressurePlateActivated(channel, pos))
         }
 
+        if let BottomTileType::Switch(channel) = self.bottom {
+            commands.trigger(SwitchToggled(channel, pos))
+        }
+
+        if let BottomTileType::Pellet(pellet) = self.bottom {
+            self.bottom = BottomTileType::Nothing;
+            commands.trigger(PelletEaten(pellet, pos, snake))
+        }
+
+        if let BottomTileType::Star = self.bottom {
+            self.bottom = BottomTileType::Nothing;
+            commands.trigger(StarCollected(pos))
+        }
+
+        if let BottomTileType::Key(key) = self.bottom {
+            self.bottom = BottomTileType::Nothing;
+            commands.trigger(KeyPickedUp(key, pos, snake))
+        }
+
+        if let BottomTileType::Crumbling = self.bottom {
+            commands.trigger(FloorStepped(pos))
+        }
+
         if let BottomTileType::Exit = self.bottom {
-            commands.trigger(SoundEvent::Exit);
-            commands.trigger(GameWinTrigger);
+            commands.trigger(ExitReached(snake, pos));
         }
     }
 
     pub fn top_removed(&mut self) {
-        self.top = TopTileType::Nothing;
+        // pits stay, the piece that was on it fell in
+        if !matches!(self.top, TopTileType::Pit) {
+            self.top = TopTileType::Nothing;
+        }
+    }
+
+    // a door may have closed on the piece, only free what the snake still holds
+    pub fn snake_removed(&mut self) {
+        if matches!(self.top, TopTileType::Snake(_)) {
+            self.top = TopTileType::Nothing;
+        }
+    }
+
+    pub fn collapse(&mut self) {
+        self.top = TopTileType::Pit;
+        self.bottom = BottomTileType::Nothing;
     }
 
     pub fn upgrade_to_door(&mut self, door: char) {
@@ -97,6 +209,21 @@ impl Tile {
         self.bottom = BottomTileType::Nothing;
     }
 
+    pub fn close_timed_door(&mut self, door: char) {
+        self.top = TopTileType::TimedDoor(door);
+    }
+
+    pub fn relock(&mut self, key: char) {
+        self.top = TopTileType::Locked(key);
+    }
+
+    pub fn downgrade_to_anti_door(&mut self, door: char) {
+        if matches!(self.top, TopTileType::Door(_)) {
+            self.top = TopTileType::Nothing;
+        }
+        self.bottom = BottomTileType::AntiDoor(door);
+    }
+
     pub fn spawn(
         &self,
         commands: &mut Commands,
@@ -108,22 +235,34 @@ impl Tile {
         pos: GridPos,
     ) {
         match self.top {
-            TopTileType::Snake => {
+            TopTileType::Snake(snake) => {
                 _ = spawn_snake_piece(
                     commands,
                     meshes,
                     materials,
-                    Color::srgb_u8(50, 200, 50),
+                    snake_color(snake),
                     pos,
                     SnakeSize(Vec2::new(40., 40.)),
                 )
                 .insert(CanMove)
                 .insert(SnakeIndex(0))
+                .insert(snake)
             }
             TopTileType::Wall => _ = spawn_wall(commands, meshes, materials, pos),
             TopTileType::Door(door_char) => {
                 spawn_door(commands, door_sprites, ui_resources, pos, door_char)
             }
+            TopTileType::Pit => spawn_pit(commands, meshes, materials, pos),
+            TopTileType::Emitter(dir) => spawn_emitter(commands, meshes, materials, pos, dir),
+            TopTileType::Receiver(channel) => {
+                spawn_receiver(commands, meshes, materials, ui_resources, pos, channel)
+            }
+            TopTileType::TimedDoor(door_char) => {
+                spawn_timed_door(commands, door_sprites, ui_resources, pos, door_char)
+            }
+            TopTileType::Locked(key) => {
+                spawn_locked_door(commands, meshes, materials, ui_resources, pos, key)
+            }
             TopTileType::Nothing => {}
         }
 
@@ -133,6 +272,9 @@ impl Tile {
                 spawn_pressure_plate(commands, door_sprites, ui_resources, pos, door_char)
             }
             BottomTileType::Spike => spawn_spike(commands, spike_sprites, pos),
+            BottomTileType::Switch(channel) => {
+                spawn_switch(commands, door_sprites, ui_resources, pos, channel)
+            }
             BottomTileType::Nothing => {}
             BottomTileType::TextHint(ref text) => {
                 _ = commands.spawn((
@@ -155,12 +297,74 @@ impl Tile {
             BottomTileType::AntiDoor(door_char) => {
                 spawn_anti_door(commands, door_sprites, ui_resources, pos, door_char)
             }
+            BottomTileType::Pellet(pellet) => {
+                spawn_pellet(commands, meshes, materials, pos, pellet)
+            }
+            BottomTileType::Star => spawn_star(commands, meshes, materials, pos),
+            BottomTileType::Crumbling => spawn_crumbling_floor(commands, meshes, materials, pos),
+            BottomTileType::Ice => spawn_ice(commands, meshes, materials, pos),
+            BottomTileType::Key(key) => {
+                spawn_key(commands, meshes, materials, ui_resources, pos, key)
+            }
         }
     }
 }
 
 #[derive(Asset, TypePath, Debug)]
-pub struct MapAsset(Box<[Box<[Tile]>]>);
+pub struct MapAsset(Box<[Box<[Tile]>]>, MapMeta);
+
+#[derive(Debug, Default, Clone, Resource)]
+pub struct MapMeta {
+    pub turn_based: bool,
+    pub par: Option<usize>,
+    pub win: WinCondition,
+    pub crumble_after: Option<usize>,
+    pub crumble_restore: bool,
+    pub door_timer: DoorTimer,
+    pub gravity: Option<IVec2>,
+    pub exit_requires: Vec<ExitCondition>,
+    pub patrols: Vec<Vec<IVec2>>,
+    pub enemy_starts: Vec<[usize; 2]>,
+    pub destinations: Vec<String>,
+    pub exits: Vec<[usize; 2]>,
+}
+
+impl MapMeta {
+    pub fn destination_of(&self, exit: [usize; 2]) -> Option<&String> {
+        self.exits
+            .iter()
+            .position(|pos| *pos == exit)
+            .and_then(|index| self.destinations.get(index))
+    }
+
+    fn parse<'a>(lines: impl Iterator<Item = (&'a str, &'a str)>) -> Self {
+        lines.fold(Self::default(), |mut meta, (key, value)| {
+            match key.trim() {
+                "turn_based" => meta.turn_based = value.trim() == "true",
+                "par" => meta.par = value.trim().parse().ok(),
+                "crumble_after" => meta.crumble_after = value.trim().parse().ok(),
+                "crumble_restore" => meta.crumble_restore = value.trim() == "true",
+                "door_timer" => meta.door_timer = DoorTimer::parse(value).unwrap_or_default(),
+                "patrol" => meta.patrols.push(parse_patrol(value)),
+                "gravity" => meta.gravity = parse_gravity(value),
+                "exit" => meta.exit_requires.extend(ExitCondition::parse(value)),
+                "next" => meta.destinations.push(level_path(value.trim())),
+                "win" => {
+                    meta.win = match value.trim() {
+                        "all" => WinCondition::All,
+                        snake => snake
+                            .parse()
+                            .map(|snake| WinCondition::Snake(SnakeId(snake)))
+                            .unwrap_or_default(),
+                    }
+                }
+                key if key.parse::<usize>().is_ok() => {}
+                key => log::warn!("unknown map setting {key}"),
+            }
+            meta
+        })
+    }
+}
 
 #[derive(Debug, Resource)]
 pub struct GameMap(pub Box<[Box<[Tile]>]>);
@@ -181,11 +385,58 @@ impl GameMap {
         let pos = pos.into();
         self.0.get_mut(pos[0]).and_then(|line| line.get_mut(pos[1]))
     }
+
+    pub fn has_closed_door(&self, channel: char) -> bool {
+        self.0.iter().flatten().any(|tile| {
+            matches!(tile.top, TopTileType::Door(door) | TopTileType::TimedDoor(door) if door == channel)
+        })
+    }
 }
 
 #[derive(Debug, Resource)]
 pub struct MapName(pub String);
 
+// the map names in `maps/levels.txt`, in the order level select numbers them
+pub fn levels() -> Vec<&'static str> {
+    include_str!("../assets/maps/levels.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+// `map_7` becomes the path of the map file
+pub fn level_path(level: &str) -> String {
+    format!("maps/{level}.game_map")
+}
+
+// the map file of a level, counting from 1
+pub fn level_map(level: usize) -> Option<String> {
+    levels()
+        .get(level.checked_sub(1)?)
+        .map(|name| level_path(name))
+}
+
+// the other way around, which level a map file is
+pub fn level_number(path: &str) -> Option<usize> {
+    levels()
+        .iter()
+        .position(|name| level_path(name) == path)
+        .map(|index| index + 1)
+}
+
+// what gets shown to the player, the last level is X and maps off the list go by their name
+pub fn level_label(path: &str) -> String {
+    match level_number(path) {
+        Some(level) if level == levels().len() => "X".to_string(),
+        Some(level) => level.to_string(),
+        None => path
+            .trim_start_matches("maps/")
+            .trim_end_matches(".game_map")
+            .to_string(),
+    }
+}
+
 #[derive(Default)]
 pub struct MapAssetLoader;
 
@@ -210,6 +461,12 @@ impl AssetLoader for MapAssetLoader {
             .map(|(l, r)| (l.trim(), r.trim()))
             .unwrap_or((map_str.as_str().trim(), ""));
 
+        let mut meta = MapMeta::parse(
+            map_text_str
+                .lines()
+                .filter_map(|line| line.split_once("::")),
+        );
+
         let text_values = map_text_str
             .lines()
             .filter_map(|line| line.split_once("::"))
@@ -250,6 +507,14 @@ impl AssetLoader for MapAssetLoader {
             .enumerate()
             .flat_map(|(y, tiles)| tiles.enumerate().map(move |(x, t)| ([x, y], t)))
         {
+            if tile == '@' {
+                meta.enemy_starts.push(pos);
+            }
+
+            if tile == '|' {
+                meta.exits.push(pos);
+            }
+
             let tile = match tile {
                 'z' | 'x' | 'c' | 'v' | 'b' | 'n' | 'm' => Tile::new(
                     None,
@@ -275,6 +540,19 @@ impl AssetLoader for MapAssetLoader {
                         )),
                     )
                 }
+                'a' | 's' | 'd' | 'f' | 'g' | 'h' | 'j' => {
+                    const DOOR_MAP: [char; 7] = ['Z', 'X', 'C', 'V', 'B', 'N', 'M'];
+                    const SWITCH_MAP: [char; 7] = ['a', 's', 'd', 'f', 'g', 'h', 'j'];
+                    Tile::new(
+                        None,
+                        Some(BottomTileType::Switch(
+                            DOOR_MAP[SWITCH_MAP
+                                .iter()
+                                .position(|c| *c == tile)
+                                .expect("switch maps should line up")],
+                        )),
+                    )
+                }
                 tile if tile.is_ascii_digit() => tile
                     .to_digit(10)
                     .and_then(|index| text_values.get(index as usize).map(|s| s.as_ref()))
@@ -291,16 +569,75 @@ impl AssetLoader for MapAssetLoader {
                         Tile::default()
                     }),
                 '#' => Tile::new(Some(TopTileType::Wall), None),
-                '%' => Tile::new(Some(TopTileType::Snake), None),
+                '%' => Tile::new(Some(TopTileType::Snake(SnakeId::default())), None),
                 '$' => Tile::new(None, Some(BottomTileType::Spike)),
                 '|' => Tile::new(None, Some(BottomTileType::Exit)),
+                '+' => Tile::new(None, Some(BottomTileType::Pellet(Pellet::Rewind))),
+                '=' => Tile::new(None, Some(BottomTileType::Pellet(Pellet::Individual))),
+                '-' => Tile::new(None, Some(BottomTileType::Pellet(Pellet::Shrink))),
+                '*' => Tile::new(None, Some(BottomTileType::Star)),
+                '.' => Tile::new(None, Some(BottomTileType::Crumbling)),
+                'k' | 'l' | 'o' | 'p' => {
+                    Tile::new(None, Some(BottomTileType::Key(tile.to_ascii_uppercase())))
+                }
+                'K' | 'L' | 'O' | 'P' => Tile::new(Some(TopTileType::Locked(tile)), None),
+                '~' => Tile::new(None, Some(BottomTileType::Ice)),
+                '>' => Tile::new(Some(TopTileType::Emitter(IVec2::X)), None),
+                '<' => Tile::new(Some(TopTileType::Emitter(IVec2::NEG_X)), None),
+                '^' => Tile::new(Some(TopTileType::Emitter(IVec2::Y)), None),
+                '!' => Tile::new(Some(TopTileType::Emitter(IVec2::NEG_Y)), None),
+                'q' | 'w' | 'e' | 'r' | 't' | 'y' | 'u' => {
+                    const DOOR_MAP: [char; 7] = ['Z', 'X', 'C', 'V', 'B', 'N', 'M'];
+                    const RECEIVER_MAP: [char; 7] = ['q', 'w', 'e', 'r', 't', 'y', 'u'];
+                    Tile::new(
+                        Some(TopTileType::Receiver(
+                            DOOR_MAP[RECEIVER_MAP
+                                .iter()
+                                .position(|c| *c == tile)
+                                .expect("receiver maps should line up")],
+                        )),
+                        None,
+                    )
+                }
+                'Q' | 'W' | 'E' | 'R' | 'T' | 'Y' | 'U' => {
+                    const DOOR_MAP: [char; 7] = ['Z', 'X', 'C', 'V', 'B', 'N', 'M'];
+                    const TIMED_DOOR_MAP: [char; 7] = ['Q', 'W', 'E', 'R', 'T', 'Y', 'U'];
+                    Tile::new(
+                        Some(TopTileType::TimedDoor(
+                            DOOR_MAP[TIMED_DOOR_MAP
+                                .iter()
+                                .position(|c| *c == tile)
+                                .expect("timed door maps should line up")],
+                        )),
+                        None,
+                    )
+                }
                 _ => Tile::default(),
             };
 
             map[pos[0]][pos[1]] = tile;
         }
 
-        Ok(MapAsset(map))
+        let mut snake_starts = map
+            .iter()
+            .enumerate()
+            .flat_map(|(x, line)| line.iter().enumerate().map(move |(y, tile)| ([x, y], tile)))
+            .filter_map(|(pos, tile)| matches!(tile.top, TopTileType::Snake(_)).then_some(pos))
+            .collect::<Vec<_>>();
+        snake_starts.sort_by_key(|pos| (Reverse(pos[1]), pos[0]));
+
+        for (snake, pos) in snake_starts.into_iter().enumerate() {
+            map[pos[0]][pos[1]].top = TopTileType::Snake(SnakeId(snake));
+        }
+
+        // patrol lines are given to the enemies in reading order
+        meta.enemy_starts
+            .sort_by_key(|pos| (Reverse(pos[1]), pos[0]));
+
+        // same for the next lines and the exits
+        meta.exits.sort_by_key(|pos| (Reverse(pos[1]), pos[0]));
+
+        Ok(MapAsset(map, meta))
     }
 
     fn extensions(&self) -> &[&str] {
@@ -314,11 +651,31 @@ pub fn map_plugin(app: &mut App) {
         .insert_resource(MapName("maps/map_1.game_map".to_string()))
         .observe(on_grid_added)
         .observe(on_grid_removed)
+        .console_command(
+            "load",
+            "load <map>, loads maps/<map>.game_map",
+            load_command,
+        )
         .add_systems(OnEnter(GameState::Loading), start_map_load)
         .add_systems(OnEnter(GameState::Gaming), init_rewinds)
         .add_systems(Update, on_map_loaded.run_if(in_state(GameState::Loading)));
 }
 
+fn load_command(
+    In(args): ConsoleArgs,
+    mut map_name: ResMut<MapName>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut console: ResMut<Console>,
+) {
+    let Some(level) = args.first() else {
+        console.print("usage: load <map>");
+        return;
+    };
+
+    map_name.0 = level_path(level);
+    next_state.set(GameState::Loading);
+}
+
 fn on_grid_added(
     trigger: Trigger<OnAdd, GridPos>,
     mut grid_ents: Query<(&mut Transform, &GridPos)>,
@@ -334,16 +691,19 @@ fn on_grid_added(
 
 fn on_grid_removed(
     trigger: Trigger<OnRemove, GridPos>,
-    grid_ents: Query<&GridPos>,
+    grid_ents: Query<(&GridPos, Has<SnakeSize>), Without<Pickup>>,
     mut map: ResMut<GameMap>,
 ) {
-    let grid_ent = grid_ents
-        .get(trigger.entity())
-        .expect("triggered ent isn't in query!");
+    let Ok((grid_ent, is_snake)) = grid_ents.get(trigger.entity()) else {
+        return;
+    };
 
-    map.get_mut(*grid_ent)
-        .expect("should be valid grid pos")
-        .top_removed();
+    let tile = map.get_mut(*grid_ent).expect("should be valid grid pos");
+    if is_snake {
+        tile.snake_removed();
+    } else {
+        tile.top_removed();
+    }
 }
 
 pub fn start_map_load(
@@ -379,7 +739,7 @@ pub fn on_map_loaded(
         return;
     }
 
-    let Some(MapAsset(map)) = assets.remove(asset_map.0.id()) else {
+    let Some(MapAsset(map, meta)) = assets.remove(asset_map.0.id()) else {
         log::warn!("missing map asset after it's loaded");
         return;
     };
@@ -402,6 +762,7 @@ pub fn on_map_loaded(
     }
 
     commands.insert_resource(GameMap(map));
+    commands.insert_resource(meta);
 
     next_state.set(GameState::Gaming)
 }
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    game_over::{GameOverState, NextLevel},
+    map::{level_map, levels, GridPos, MapMeta, MapName, Pickup},
+    rewind::RewindConfirmed,
+    snake::{Move, RewindCounter, Sliding},
+    sounds::SoundEvent,
+    GameState,
+};
+
+#[derive(Debug, Event)]
+pub struct StarCollected(pub [usize; 2]);
+
+#[derive(Debug, Component)]
+pub struct Star;
+
+// what happened in the current attempt at a level
+#[derive(Debug, Resource, Default)]
+pub struct LevelStats {
+    pub stars: usize,
+    pub stars_total: usize,
+    pub moves: usize,
+    pub rewinds: usize,
+}
+
+impl LevelStats {
+    pub fn par_met(&self, meta: &MapMeta) -> Option<bool> {
+        meta.par.map(|par| self.moves <= par)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LevelRecord {
+    pub stars: usize,
+    pub stars_total: usize,
+    // only ever set on maps that have a par
+    pub par_met: bool,
+    pub rewinds_left: isize,
+    pub best_moves: Option<usize>,
+    pub fewest_rewinds: Option<usize>,
+    pub branches: Vec<String>,
+}
+
+// best results per map name, a map with a record has been beaten
+#[derive(Debug, Resource, Default)]
+pub struct LevelProgress(pub HashMap<String, LevelRecord>);
+
+impl LevelProgress {
+    pub fn completed(&self, level: usize) -> bool {
+        level_map(level).is_some_and(|path| self.0.contains_key(&path))
+    }
+
+    // the first level is always open, the rest open up once the one before or a branch leads there
+    pub fn unlocked(&self, level: usize) -> bool {
+        let Some(path) = level_map(level) else {
+            return false;
+        };
+        level == 1
+            || self.completed(level)
+            || self.completed(level - 1)
+            || self
+                .0
+                .values()
+                .any(|record| record.branches.contains(&path))
+    }
+
+    pub fn first_unbeaten(&self) -> Option<usize> {
+        (1..=levels().len()).find(|level| !self.completed(*level))
+    }
+}
+
+pub fn objectives_plugin(app: &mut App) {
+    app.init_resource::<LevelStats>()
+        .init_resource::<LevelProgress>()
+        .observe(on_star_collected)
+        .observe(count_moves)
+        .observe(count_rewinds)
+        .add_systems(OnEnter(GameState::Gaming), reset_level_stats)
+        .add_systems(OnEnter(GameOverState::Win), record_progress);
+}
+
+pub fn spawn_star(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(RegularPolygon::new(14., 5))),
+            material: materials.add(Color::srgb_u8(250, 220, 50)),
+            transform: Transform::from_xyz(0., 0., 40.),
+            ..default()
+        },
+        Star,
+        Pickup,
+        grid_pos,
+        StateScoped(GameState::Gaming),
+    ));
+}
+
+fn reset_level_stats(mut stats: ResMut<LevelStats>, stars: Query<(), With<Star>>) {
+    *stats = LevelStats {
+        stars_total: stars.iter().count(),
+        ..default()
+    };
+}
+
+// steps taken while sliding on ice are part of the move that started the slide
+fn count_moves(
+    trigger: Trigger<OnAdd, Move>,
+    sliding: Query<(), With<Sliding>>,
+    mut stats: ResMut<LevelStats>,
+) {
+    if !sliding.contains(trigger.entity()) {
+        stats.moves += 1;
+    }
+}
+
+fn count_rewinds(_: Trigger<RewindConfirmed>, mut stats: ResMut<LevelStats>) {
+    stats.rewinds += 1;
+}
+
+fn on_star_collected(
+    trigger: Trigger<StarCollected>,
+    mut commands: Commands,
+    stars: Query<(Entity, &GridPos), With<Star>>,
+    mut stats: ResMut<LevelStats>,
+) {
+    if let Some(ent) = stars
+        .iter()
+        .find_map(|(ent, grid_pos)| (grid_pos.0 == trigger.event().0).then_some(ent))
+    {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    stats.stars += 1;
+    commands.trigger(SoundEvent::Star);
+}
+
+fn record_progress(
+    stats: Res<LevelStats>,
+    rewinds: Res<RewindCounter>,
+    meta: Res<MapMeta>,
+    map_name: Res<MapName>,
+    next_level: Res<NextLevel>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    let record = progress.0.entry(map_name.0.clone()).or_default();
+
+    record.stars = record.stars.max(stats.stars);
+    record.stars_total = stats.stars_total;
+    record.par_met |= stats.par_met(&meta).unwrap_or(false);
+    record.rewinds_left = record.rewinds_left.max(rewinds.individual.max(0));
+    record.best_moves = Some(
+        record
+            .best_moves
+            .map_or(stats.moves, |best| best.min(stats.moves)),
+    );
+    record.fewest_rewinds = Some(
+        record
+            .fewest_rewinds
+            .map_or(stats.rewinds, |fewest| fewest.min(stats.rewinds)),
+    );
+
+    if let Some(destination) = &next_level.0 {
+        if !record.branches.contains(destination) {
+            record.branches.push(destination.clone());
+        }
+    }
+}
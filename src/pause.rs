@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    game_over::GameOverState,
+    input::GamepadInput,
+    keymap::{Action, Keymap},
+    main_menu::{
+        create_button, spawn_setting_buttons, MainMenuState, NORMAL_BUTTON, PRESSED_BUTTON,
+    },
+    sounds::SoundEvent,
+    title::UiResources,
+    GameState,
+};
+
+#[derive(Debug, SubStates, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[source(GameState = GameState::Gaming)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+    Settings,
+}
+
+#[derive(Debug, Component, Clone, Copy)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Settings,
+    LevelSelect,
+    Quit,
+    Back,
+}
+
+pub fn pause_plugin(app: &mut App) {
+    app.add_sub_state::<PauseState>()
+        .enable_state_scoped_entities::<PauseState>()
+        .add_systems(OnEnter(PauseState::Paused), (setup_pause_ui, pause_time))
+        .add_systems(OnEnter(PauseState::Settings), setup_settings_ui)
+        .add_systems(OnEnter(PauseState::Running), resume_time)
+        .add_systems(OnExit(GameState::Gaming), resume_time)
+        .add_systems(
+            Update,
+            (toggle_pause, pause_button).run_if(in_state(GameState::Gaming)),
+        );
+}
+
+pub fn is_paused(state: Option<Res<State<PauseState>>>) -> bool {
+    state.is_some_and(|state| *state.get() != PauseState::Running)
+}
+
+// timers and animations all read virtual time, so stopping it freezes them mid step
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    gamepad: GamepadInput,
+    game_over: Res<State<GameOverState>>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    // the game over screens have their own way back to the menu
+    if *game_over.get() != GameOverState::None {
+        return;
+    }
+
+    if !keymap.just_pressed(Action::Menu, &keys) && !gamepad.just_pressed(GamepadButtonType::Start)
+    {
+        return;
+    }
+
+    next_pause.set(match pause.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+        PauseState::Settings => PauseState::Paused,
+    });
+}
+
+fn setup_pause_ui(commands: Commands, ui_resources: Res<UiResources>) {
+    setup_pause_menu(
+        commands,
+        &ui_resources,
+        PauseState::Paused,
+        |parent, style| {
+            for (button, label) in [
+                (PauseButton::Resume, "Resume"),
+                (PauseButton::Restart, "Restart"),
+                (PauseButton::Settings, "Settings"),
+                (PauseButton::LevelSelect, "Level Select"),
+                (PauseButton::Quit, "Quit to Menu"),
+            ] {
+                create_button(parent, label, style.clone(), button);
+            }
+        },
+    );
+}
+
+fn setup_settings_ui(commands: Commands, ui_resources: Res<UiResources>) {
+    setup_pause_menu(
+        commands,
+        &ui_resources,
+        PauseState::Settings,
+        |parent, style| {
+            _ = spawn_setting_buttons(
+                parent,
+                TextStyle {
+                    font_size: 15.,
+                    ..style.clone()
+                },
+            );
+            create_button(parent, "Go Back", style, PauseButton::Back);
+        },
+    );
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    ui_resources: &UiResources,
+    state: PauseState,
+    menu_func: impl FnOnce(&mut ChildBuilder, TextStyle),
+) {
+    let button_style = TextStyle {
+        font_size: 30.,
+        color: Color::srgba(0.99, 0.99, 0.99, 1.),
+        font: ui_resources.font.clone(),
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.6).into(),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 50.,
+                    ..button_style.clone()
+                },
+            ));
+            menu_func(parent, button_style);
+        })
+        .insert(StateScoped(state));
+}
+
+fn pause_button(
+    mut commands: Commands,
+    mut button: Query<
+        (
+            &mut BorderColor,
+            &mut BackgroundColor,
+            &Interaction,
+            &PauseButton,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_menu: ResMut<NextState<MainMenuState>>,
+) {
+    for (mut border_color, mut color, interaction, button) in button.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = PRESSED_BUTTON.into();
+                border_color.0 = Color::BLACK;
+
+                match button {
+                    PauseButton::Resume => next_pause.set(PauseState::Running),
+                    PauseButton::Restart => next_state.set(GameState::Loading),
+                    PauseButton::Settings => next_pause.set(PauseState::Settings),
+                    PauseButton::LevelSelect => {
+                        next_state.set(GameState::MainMenu);
+                        next_menu.set(MainMenuState::LevelSelection);
+                    }
+                    PauseButton::Quit => next_state.set(GameState::MainMenu),
+                    PauseButton::Back => next_pause.set(PauseState::Paused),
+                }
+                commands.trigger(SoundEvent::Select);
+            }
+            Interaction::Hovered => {
+                border_color.0 = PRESSED_BUTTON;
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+                border_color.0 = NORMAL_BUTTON;
+            }
+        }
+    }
+}
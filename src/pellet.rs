@@ -0,0 +1,102 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    fade_out::FadeOutThisEnt,
+    map::{GameMap, GridPos, Pickup},
+    snake::{CanMove, Move, RewindCounter, SnakeColor, SnakeId, SnakeIndex},
+    sounds::SoundEvent,
+    GameState,
+};
+
+const INDIVIDUAL_REWINDS_GAINED: isize = 5;
+const TAIL_SEGMENTS_REMOVED: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pellet {
+    Rewind,
+    Individual,
+    Shrink,
+}
+
+#[derive(Debug, Event)]
+pub struct PelletEaten(pub Pellet, pub [usize; 2], pub SnakeId);
+
+#[derive(Debug, Component)]
+struct PelletTile;
+
+pub fn pellet_plugin(app: &mut App) {
+    app.observe(on_pellet_eaten);
+}
+
+pub fn spawn_pellet(
+    commands: &mut Commands<'_, '_>,
+    meshes: &mut ResMut<'_, Assets<Mesh>>,
+    materials: &mut ResMut<'_, Assets<ColorMaterial>>,
+    grid_pos: GridPos,
+    pellet: Pellet,
+) {
+    let color = match pellet {
+        Pellet::Rewind => Color::srgb_u8(255, 153, 153),
+        Pellet::Individual => Color::srgb_u8(255, 220, 120),
+        Pellet::Shrink => Color::srgb_u8(150, 120, 255),
+    };
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle::new(10.))),
+            material: materials.add(color),
+            transform: Transform::from_xyz(0., 0., 40.),
+            ..default()
+        },
+        PelletTile,
+        Pickup,
+        grid_pos,
+        StateScoped(GameState::Gaming),
+    ));
+}
+
+fn on_pellet_eaten(
+    trigger: Trigger<PelletEaten>,
+    mut commands: Commands,
+    pellets: Query<(Entity, &GridPos), With<PelletTile>>,
+    snake_pieces: Query<
+        (Entity, &SnakeIndex, &SnakeColor, &GridPos, &SnakeId),
+        (Without<CanMove>, Without<Move>),
+    >,
+    mut rewinds: ResMut<RewindCounter>,
+    mut map: ResMut<GameMap>,
+) {
+    let PelletEaten(pellet, pos, snake) = *trigger.event();
+
+    if let Some(ent) = pellets
+        .iter()
+        .find_map(|(ent, grid_pos)| (grid_pos.0 == pos).then_some(ent))
+    {
+        commands.entity(ent).despawn_recursive();
+    }
+
+    match pellet {
+        Pellet::Rewind => rewinds.total += 1,
+        Pellet::Individual => rewinds.individual += INDIVIDUAL_REWINDS_GAINED,
+        Pellet::Shrink => {
+            let mut tail = snake_pieces
+                .iter()
+                .filter(|piece| *piece.4 == snake)
+                .collect::<Vec<_>>();
+            tail.sort_by_key(|piece| piece.1 .0);
+
+            for (ent, _, color, grid_pos, _) in tail.into_iter().take(TAIL_SEGMENTS_REMOVED) {
+                if let Some(map_tile) = map.get_mut(*grid_pos) {
+                    map_tile.top_removed();
+                }
+
+                commands.entity(ent).insert(FadeOutThisEnt(color.0));
+            }
+        }
+    }
+
+    commands.trigger(SoundEvent::Pellet);
+}
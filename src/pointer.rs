@@ -0,0 +1,268 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    input::MoveQueue,
+    map::{GameMap, GridPos, GRID_CELL_SIZE},
+    rewind::{RewindConfirmed, RewindMode},
+    snake::{ActiveSnake, CanMove, Move, SnakeId},
+    sounds::SoundEvent,
+    title::UiResources,
+    GameState, GameplaySet,
+};
+
+// in logical pixels, anything shorter is a tap
+const SWIPE_DISTANCE: f32 = 30.;
+
+// longer paths are cut off, they'd take ages to walk anyway
+const MAX_PATH_LENGTH: usize = 64;
+
+const CONTROL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const CONTROL_PRESSED: Color = Color::srgb(0.30, 0.30, 0.30);
+
+#[derive(Debug, Clone, Copy)]
+enum Gesture {
+    Tap(Vec2),
+    Swipe(IVec2),
+}
+
+#[derive(Debug, Component, Clone, Copy)]
+enum RewindControl {
+    Less,
+    More,
+    Rewind,
+}
+
+// where the current press started, `None` when it started on a button
+#[derive(Debug, Default)]
+struct PointerPress(Option<Vec2>);
+
+pub fn pointer_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Gaming), setup_rewind_controls)
+        .add_systems(
+            Update,
+            (handle_pointer, rewind_control_button).in_set(GameplaySet::Input),
+        );
+}
+
+fn setup_rewind_controls(mut commands: Commands, ui_resources: Res<UiResources>) {
+    let text_style = TextStyle {
+        font_size: 25.0,
+        color: Color::srgba_u8(153, 153, 153, 255),
+        font: ui_resources.font.clone(),
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(5.),
+                right: Val::Px(10.),
+                column_gap: Val::Px(5.),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(StateScoped(GameState::Gaming))
+        .with_children(|parent| {
+            for (control, label, width) in [
+                (RewindControl::Less, "-", 50.),
+                (RewindControl::More, "+", 50.),
+                (RewindControl::Rewind, "rewind", 100.),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(width),
+                                height: Val::Px(50.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: CONTROL_BUTTON.into(),
+                            ..default()
+                        },
+                        control,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+            }
+        });
+}
+
+// the same rewind mode the keyboard scrubs, just without the keyboard
+fn rewind_control_button(
+    mut commands: Commands,
+    mut buttons: Query<
+        (&mut BackgroundColor, &Interaction, &RewindControl),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut mode: ResMut<RewindMode>,
+) {
+    for (mut color, interaction, control) in buttons.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            *color = CONTROL_BUTTON.into();
+            continue;
+        }
+        *color = CONTROL_PRESSED.into();
+
+        match control {
+            RewindControl::Less if mode.amount.is_some() => mode.scrub(-1),
+            RewindControl::More => mode.scrub(1),
+            RewindControl::Rewind => match mode.confirm() {
+                Some(amount) => commands.trigger(RewindConfirmed(amount)),
+                None => continue,
+            },
+            RewindControl::Less => continue,
+        }
+        commands.trigger(SoundEvent::Select);
+    }
+}
+
+fn handle_pointer(
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    buttons: Query<&Interaction, With<Button>>,
+    heads: Query<(&GridPos, &SnakeId), Or<(With<CanMove>, With<Move>)>>,
+    active: Res<ActiveSnake>,
+    map: Res<GameMap>,
+    rewind: Res<RewindMode>,
+    mut queue: ResMut<MoveQueue>,
+    mut press: Local<PointerPress>,
+) {
+    // taps and swipes would only line up moves for after the rewind
+    if rewind.amount.is_some() {
+        press.0 = None;
+        return;
+    }
+
+    let on_button = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    let mut gesture = None;
+
+    let cursor = window
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    if mouse.just_pressed(MouseButton::Left) {
+        press.0 = cursor.filter(|_| !on_button);
+    }
+    if mouse.just_released(MouseButton::Left) {
+        gesture = press.0.take().zip(cursor).map(to_gesture);
+    }
+
+    for touch in touches.iter_just_pressed() {
+        press.0 = (!on_button).then_some(touch.start_position());
+    }
+    for touch in touches.iter_just_released() {
+        gesture = press
+            .0
+            .take()
+            .map(|start| to_gesture((start, touch.position())));
+    }
+
+    let Some(gesture) = gesture else {
+        return;
+    };
+
+    match gesture {
+        // one cell each, like a key tap
+        Gesture::Swipe(dir) => queue.push(dir),
+        Gesture::Tap(screen_pos) => {
+            let Some(head) = heads
+                .iter()
+                .find_map(|(pos, snake)| (*snake == active.0).then_some(pos.0))
+            else {
+                return;
+            };
+            let Some(target) = camera
+                .get_single()
+                .ok()
+                .and_then(|(camera, transform)| camera.viewport_to_world_2d(transform, screen_pos))
+                .and_then(world_to_grid)
+            else {
+                return;
+            };
+
+            if let Some(path) = find_path(&map, head, target) {
+                queue.0 = path.into();
+            }
+        }
+    }
+}
+
+fn to_gesture((start, end): (Vec2, Vec2)) -> Gesture {
+    let delta = end - start;
+    if delta.length() < SWIPE_DISTANCE {
+        return Gesture::Tap(end);
+    }
+
+    // the screen grows downwards, the grid upwards
+    Gesture::Swipe(if delta.x.abs() > delta.y.abs() {
+        IVec2::new(delta.x.signum() as i32, 0)
+    } else {
+        IVec2::new(0, -delta.y.signum() as i32)
+    })
+}
+
+fn world_to_grid(world: Vec2) -> Option<[usize; 2]> {
+    let cell = (world / GRID_CELL_SIZE).round();
+    Some([
+        usize::try_from(cell.x as isize).ok()?,
+        usize::try_from(cell.y as isize).ok()?,
+    ])
+}
+
+fn is_free(map: &GameMap, pos: [usize; 2]) -> bool {
+    map.get(pos).is_some_and(|tile| !tile.is_occupied())
+}
+
+// breadth first over free tiles, the directions to walk from `from` to `to`
+fn find_path(map: &GameMap, from: [usize; 2], to: [usize; 2]) -> Option<Vec<IVec2>> {
+    if from == to || !is_free(map, to) {
+        return None;
+    }
+
+    let mut came_from = HashMap::from([(from, (from, IVec2::ZERO))]);
+    let mut frontier = VecDeque::from([from]);
+
+    while let Some(pos) = frontier.pop_front() {
+        if pos == to {
+            break;
+        }
+
+        for dir in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+            let Some(next) = usize::try_from(pos[0] as isize + dir.x as isize)
+                .ok()
+                .zip(usize::try_from(pos[1] as isize + dir.y as isize).ok())
+                .map(|(x, y)| [x, y])
+            else {
+                continue;
+            };
+
+            if came_from.contains_key(&next) || !is_free(map, next) {
+                continue;
+            }
+
+            came_from.insert(next, (pos, dir));
+            frontier.push_back(next);
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut pos = to;
+    while pos != from {
+        let (previous, dir) = came_from.get(&pos)?;
+        path.push(*dir);
+        pos = *previous;
+    }
+    path.reverse();
+
+    (path.len() <= MAX_PATH_LENGTH).then_some(path)
+}
@@ -0,0 +1,273 @@
+use bevy::prelude::*;
+
+use crate::{
+    fade_out::FadeOutThisEnt,
+    input::{GamepadInput, MoveQueue},
+    keymap::{Action, Keymap},
+    map::{GameMap, GridPos},
+    snake::{ActiveSnake, CanMove, RewindCounter, SnakeColor, SnakeId, SnakeIndex, SnakeRewound},
+    turn::TurnEvent,
+    GameState, GameplaySet,
+};
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const PREVIEW_COLOR: Color = Color::srgb(1.5, 0.3, 0.3);
+
+// the rewind being lined up, `amount` is `None` outside of rewind mode
+#[derive(Debug, Resource, Default)]
+pub struct RewindMode {
+    pub amount: Option<usize>,
+    // the first digit typed replaces the scrubbed amount, the next ones build on it
+    typing: bool,
+}
+
+impl RewindMode {
+    pub fn scrub(&mut self, by: isize) {
+        self.typing = false;
+        self.amount = match self.amount {
+            None => Some(1),
+            Some(amount) => amount.checked_add_signed(by).filter(|amount| *amount != 0),
+        };
+    }
+
+    fn type_digit(&mut self, digit: usize) {
+        self.amount = Some(match self.amount {
+            Some(amount) if self.typing => amount.saturating_mul(10).saturating_add(digit),
+            _ => digit,
+        });
+        self.typing = true;
+    }
+
+    pub fn cancel(&mut self) {
+        *self = Self::default();
+    }
+
+    // leaves rewind mode, handing back the amount to rewind by
+    pub fn confirm(&mut self) -> Option<usize> {
+        let amount = self.amount.filter(|amount| *amount != 0);
+        self.cancel();
+        amount
+    }
+}
+
+// removes that many pieces from the active snake, head first
+#[derive(Debug, Event)]
+pub struct RewindConfirmed(pub usize);
+
+pub fn rewind_plugin(app: &mut App) {
+    app.init_resource::<RewindMode>()
+        .observe(on_rewind_confirmed)
+        .add_systems(
+            OnEnter(GameState::Gaming),
+            |mut mode: ResMut<RewindMode>| mode.cancel(),
+        )
+        .add_systems(
+            Update,
+            (
+                (handle_rewind_keys, handle_rewind_gamepad, drop_queued_moves)
+                    .chain()
+                    .in_set(GameplaySet::Input),
+                preview_rewind.in_set(GameplaySet::After),
+            ),
+        );
+}
+
+// the rewind key opens rewind mode, where moving scrubs and digits type the amount
+fn handle_rewind_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keymap: Res<Keymap>,
+    mut mode: ResMut<RewindMode>,
+    heads: Query<&SnakeId, With<CanMove>>,
+    active: Res<ActiveSnake>,
+) {
+    if !heads.iter().any(|snake| *snake == active.0) {
+        return;
+    }
+
+    if keymap.just_pressed(Action::Rewind, &keys) {
+        mode.scrub(0);
+        return;
+    }
+
+    if mode.amount.is_none() {
+        return;
+    }
+
+    if keymap.just_pressed(Action::ClearRewind, &keys) {
+        mode.cancel();
+        return;
+    }
+
+    for (action, by) in [
+        (Action::MoveUp, 1),
+        (Action::MoveRight, 1),
+        (Action::MoveDown, -1),
+        (Action::MoveLeft, -1),
+    ] {
+        if keymap.just_pressed(action, &keys) {
+            mode.scrub(by);
+        }
+    }
+
+    for digit in keys
+        .get_just_pressed()
+        .filter_map(|key| DIGITS.iter().position(|digit| digit == key))
+    {
+        mode.type_digit(digit);
+    }
+
+    if keymap.just_pressed(Action::Confirm, &keys) {
+        if let Some(amount) = mode.confirm() {
+            commands.trigger(RewindConfirmed(amount));
+        }
+    }
+}
+
+// shoulders scrub the rewind amount, south confirms it and east cancels
+fn handle_rewind_gamepad(
+    mut commands: Commands,
+    gamepad: GamepadInput,
+    mut mode: ResMut<RewindMode>,
+    heads: Query<&SnakeId, With<CanMove>>,
+    active: Res<ActiveSnake>,
+) {
+    if !heads.iter().any(|snake| *snake == active.0) {
+        return;
+    }
+
+    if gamepad.just_pressed(GamepadButtonType::RightTrigger) {
+        mode.scrub(1);
+    }
+    if gamepad.just_pressed(GamepadButtonType::LeftTrigger) && mode.amount.is_some() {
+        mode.scrub(-1);
+    }
+    if gamepad.just_pressed(GamepadButtonType::East) {
+        mode.cancel();
+    }
+    if gamepad.just_pressed(GamepadButtonType::South) {
+        if let Some(amount) = mode.confirm() {
+            commands.trigger(RewindConfirmed(amount));
+        }
+    }
+}
+
+// moves lined up before rewind mode opened would otherwise go off once it closes
+fn drop_queued_moves(mode: Res<RewindMode>, mut queue: ResMut<MoveQueue>) {
+    if mode.amount.is_some() {
+        queue.0.clear();
+    }
+}
+
+// tints the pieces a confirm would remove, the head always keeps one piece behind
+fn preview_rewind(
+    mut mode: ResMut<RewindMode>,
+    pieces: Query<(&SnakeIndex, &SnakeId, &SnakeColor, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    active: Res<ActiveSnake>,
+) {
+    let mut ordered = pieces
+        .iter()
+        .filter(|piece| *piece.1 == active.0)
+        .map(|piece| piece.0 .0)
+        .collect::<Vec<_>>();
+    ordered.sort();
+    ordered.reverse();
+
+    if let Some(amount) = mode.amount {
+        let most = ordered.len().saturating_sub(1);
+        if most == 0 {
+            mode.cancel();
+        } else if amount > most {
+            mode.amount = Some(most);
+        }
+    }
+
+    let cutoff = mode
+        .amount
+        .and_then(|amount| ordered.get(amount.checked_sub(1)?))
+        .copied();
+
+    for (index, snake, color, material) in pieces.iter() {
+        let tint = match cutoff {
+            Some(cutoff) if *snake == active.0 && index.0 >= cutoff => PREVIEW_COLOR,
+            _ => color.0,
+        };
+
+        if materials
+            .get(material.id())
+            .is_some_and(|material| material.color != tint)
+        {
+            if let Some(material) = materials.get_mut(material.id()) {
+                material.color = tint;
+            }
+        }
+    }
+}
+
+fn on_rewind_confirmed(
+    trigger: Trigger<RewindConfirmed>,
+    snake_pieces: Query<(Entity, &SnakeIndex, &SnakeColor, &GridPos, &SnakeId)>,
+    mut map: ResMut<GameMap>,
+    mut commands: Commands,
+    mut rewinds: ResMut<RewindCounter>,
+    mut queue: ResMut<MoveQueue>,
+    active: Res<ActiveSnake>,
+) {
+    queue.0.clear();
+
+    let mut snake_ordered = snake_pieces
+        .iter()
+        .filter(|piece| *piece.4 == active.0)
+        .collect::<Vec<_>>();
+    snake_ordered.sort_by(|other, piece| other.1 .0.cmp(&piece.1 .0));
+    snake_ordered.reverse();
+
+    // the tail always stays, whatever amount came in
+    let steps = trigger.event().0.min(snake_ordered.len().saturating_sub(1));
+    if steps == 0 {
+        return;
+    }
+
+    commands.trigger(SnakeRewound(
+        (0..steps)
+            .filter_map(|i| snake_ordered.get(i))
+            .map(|piece| piece.3 .0)
+            .collect(),
+    ));
+
+    (0..steps)
+        .filter_map(|i| snake_ordered.get(i))
+        .for_each(|piece| {
+            if let Some(map_tile) = map.get_mut(*piece.3) {
+                map_tile.top_removed();
+            }
+
+            commands.entity(piece.0).insert(FadeOutThisEnt(piece.2 .0));
+        });
+
+    if let Some(mut new_head) = snake_ordered
+        .get(steps)
+        .map(|new_head| new_head.0)
+        .and_then(|new_head| commands.get_entity(new_head))
+    {
+        new_head.insert(CanMove);
+    }
+
+    rewinds.total -= 1;
+    rewinds.individual -= steps as isize;
+
+    commands.trigger(TurnEvent);
+}
@@ -0,0 +1,158 @@
+use bevy::{log, prelude::*};
+
+use crate::objectives::{LevelProgress, LevelRecord};
+
+const SAVE_NAME: &str = "save.txt";
+
+// where the save and config files live, swapped per platform or by inserting a `SaveBackend` first
+pub trait SaveStorage: Send + Sync + 'static {
+    fn read(&self, name: &str) -> Option<String>;
+    fn write(&self, name: &str, contents: &str) -> Result<(), String>;
+}
+
+#[derive(Resource)]
+pub struct SaveBackend(pub Box<dyn SaveStorage>);
+
+// files in the platform config dir
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage(pub std::path::PathBuf);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for FileStorage {
+    fn default() -> Self {
+        let dir = directories::ProjectDirs::from("", "", "ReSnaked")
+            .map(|dirs| dirs.config_dir().to_path_buf())
+            .unwrap_or_default();
+        Self(dir)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        std::fs::read_to_string(self.0.join(name)).ok()
+    }
+
+    fn write(&self, name: &str, contents: &str) -> Result<(), String> {
+        std::fs::create_dir_all(&self.0).map_err(|err| err.to_string())?;
+        std::fs::write(self.0.join(name), contents).map_err(|err| err.to_string())
+    }
+}
+
+// the page's local storage, one key per file behind a shared prefix
+#[cfg(target_arch = "wasm32")]
+pub struct BrowserStorage(pub &'static str);
+
+#[cfg(target_arch = "wasm32")]
+impl Default for BrowserStorage {
+    fn default() -> Self {
+        Self("resnaked_")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveStorage for BrowserStorage {
+    fn read(&self, name: &str) -> Option<String> {
+        web_sys::window()?
+            .local_storage()
+            .ok()??
+            .get_item(&format!("{}{name}", self.0))
+            .ok()?
+    }
+
+    fn write(&self, name: &str, contents: &str) -> Result<(), String> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or("no local storage")?
+            .set_item(&format!("{}{name}", self.0), contents)
+            .map_err(|err| format!("{err:?}"))
+    }
+}
+
+impl Default for SaveBackend {
+    fn default() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Self(Box::new(FileStorage::default()));
+
+        #[cfg(target_arch = "wasm32")]
+        return Self(Box::new(BrowserStorage::default()));
+    }
+}
+
+pub fn save_plugin(app: &mut App) {
+    app.init_resource::<SaveBackend>()
+        .add_systems(Startup, load_progress)
+        .add_systems(Update, save_progress);
+}
+
+fn load_progress(backend: Res<SaveBackend>, mut progress: ResMut<LevelProgress>) {
+    if let Some(save) = backend.0.read(SAVE_NAME) {
+        *progress = from_save(&save);
+    }
+}
+
+fn save_progress(backend: Res<SaveBackend>, progress: Res<LevelProgress>) {
+    if !progress.is_changed() || progress.is_added() {
+        return;
+    }
+
+    if let Err(err) = backend.0.write(SAVE_NAME, &to_save(&progress)) {
+        log::warn!("couldn't save the progress: {err}");
+    }
+}
+
+// one `map::field=value ...` line per beaten map, unknown fields are skipped
+fn from_save(save: &str) -> LevelProgress {
+    LevelProgress(
+        save.lines()
+            .filter_map(|line| line.split_once("::"))
+            .map(|(map, fields)| {
+                let mut record = LevelRecord::default();
+                for (field, value) in fields
+                    .split_whitespace()
+                    .filter_map(|field| field.split_once('='))
+                {
+                    match field {
+                        "stars" => record.stars = value.parse().unwrap_or_default(),
+                        "stars_total" => record.stars_total = value.parse().unwrap_or_default(),
+                        "par_met" => record.par_met = value.parse().unwrap_or_default(),
+                        "rewinds_left" => record.rewinds_left = value.parse().unwrap_or_default(),
+                        "best_moves" => record.best_moves = value.parse().ok(),
+                        "fewest_rewinds" => record.fewest_rewinds = value.parse().ok(),
+                        "branches" => {
+                            record.branches = value.split(',').map(str::to_string).collect()
+                        }
+                        _ => {}
+                    }
+                }
+                (map.trim().to_string(), record)
+            })
+            .collect(),
+    )
+}
+
+fn to_save(progress: &LevelProgress) -> String {
+    let mut maps = progress.0.iter().collect::<Vec<_>>();
+    maps.sort_by_key(|(map, _)| *map);
+
+    maps.into_iter()
+        .map(|(map, record)| {
+            let mut fields = vec![
+                format!("stars={}", record.stars),
+                format!("stars_total={}", record.stars_total),
+                format!("par_met={}", record.par_met),
+                format!("rewinds_left={}", record.rewinds_left),
+            ];
+            fields.extend(record.best_moves.map(|moves| format!("best_moves={moves}")));
+            fields.extend(
+                record
+                    .fewest_rewinds
+                    .map(|rewinds| format!("fewest_rewinds={rewinds}")),
+            );
+            if !record.branches.is_empty() {
+                fields.push(format!("branches={}", record.branches.join(",")));
+            }
+            format!("{map}::{}\n", fields.join(" "))
+        })
+        .collect()
+}
@@ -0,0 +1,247 @@
+use bevy::{
+    audio::{GlobalVolume, Volume},
+    core_pipeline::bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings},
+    log,
+    prelude::*,
+    utils::HashMap,
+};
+use std::str::FromStr;
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::window::{PrimaryWindow, WindowMode};
+
+use crate::{input::KeyRepeat, save::SaveBackend, snake::AnimationSpeed};
+
+const SETTINGS_NAME: &str = "settings.cfg";
+
+// what the settings menu cycles through, anything else in the file is ignored
+pub const VOLUME_OPTIONS: [f32; 5] = [0., 0.25, 0.5, 0.75, 1.];
+pub const BLOOM_INTENSITY_OPTIONS: [f32; 4] = [0.1, 0.2, 0.3, 0.5];
+pub const WINDOW_WIDTH_OPTIONS: [f32; 4] = [960., 1280., 1600., 1920.];
+pub const ANIMATION_SPEED_OPTIONS: [f32; 4] = [0.5, 1., 1.5, 2.];
+pub const REPEAT_DELAY_OPTIONS: [f32; 3] = [0.15, 0.25, 0.4];
+pub const REPEAT_INTERVAL_OPTIONS: [f32; 4] = [0.05, 0.1, 0.2, 0.3];
+
+// audio, video and palette options, key repeat and animation speed keep their own resources
+#[derive(Debug, Resource, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub bloom: bool,
+    pub bloom_intensity: f32,
+    pub fullscreen: bool,
+    // the height follows at 16:9
+    pub window_width: f32,
+    pub colourblind: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            effects_volume: 1.,
+            bloom: true,
+            bloom_intensity: 0.2,
+            fullscreen: false,
+            window_width: 1280.,
+            colourblind: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn window_height(&self) -> f32 {
+        (self.window_width * 9. / 16.).round()
+    }
+}
+
+pub fn settings_plugin(app: &mut App) {
+    app.init_resource::<SaveBackend>();
+    let (settings, repeat, speed) = load_settings(app.world().resource::<SaveBackend>());
+
+    app.insert_resource(settings)
+        .insert_resource(repeat)
+        .insert_resource(speed)
+        .add_systems(Update, (apply_volume, apply_bloom, save_settings));
+
+    // the page sizes the canvas on the web
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(Update, apply_window);
+}
+
+fn bloom_settings(intensity: f32) -> BloomSettings {
+    BloomSettings {
+        intensity,
+        low_frequency_boost: 0.7,
+        low_frequency_boost_curvature: 0.95,
+        high_pass_frequency: 1.0,
+        prefilter_settings: BloomPrefilterSettings {
+            threshold: 0.0,
+            threshold_softness: 0.0,
+        },
+        composite_mode: BloomCompositeMode::EnergyConserving,
+    }
+}
+
+fn apply_volume(settings: Res<Settings>, mut volume: ResMut<GlobalVolume>) {
+    if settings.is_changed() {
+        volume.volume = Volume::new(settings.master_volume);
+    }
+}
+
+fn apply_bloom(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    cameras: Query<Entity, With<Camera2d>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for camera in cameras.iter() {
+        if settings.bloom {
+            commands
+                .entity(camera)
+                .insert(bloom_settings(settings.bloom_intensity));
+        } else {
+            commands.entity(camera).remove::<BloomSettings>();
+        }
+    }
+}
+
+// only after a change on the settings screen, the window starts the way the platform made it
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_window(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied: Local<Option<(bool, f32)>>,
+) {
+    let wanted = (settings.fullscreen, settings.window_width);
+    if applied
+        .replace(wanted)
+        .is_none_or(|applied| applied == wanted)
+    {
+        return;
+    }
+
+    let Ok(mut window) = window.get_single_mut() else {
+        return;
+    };
+
+    window.mode = if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    window
+        .resolution
+        .set(settings.window_width, settings.window_height());
+}
+
+// same `key::value` lines as the keymap, missing, broken or unoffered values keep their default
+fn load_settings(backend: &SaveBackend) -> (Settings, KeyRepeat, AnimationSpeed) {
+    let config = backend.0.read(SETTINGS_NAME).unwrap_or_default();
+    let values = config
+        .lines()
+        .filter_map(|line| line.split_once("::"))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect::<HashMap<_, _>>();
+
+    fn get<T: FromStr>(values: &HashMap<&str, &str>, name: &str, default: T) -> T {
+        values
+            .get(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn get_option(values: &HashMap<&str, &str>, name: &str, options: &[f32], default: f32) -> f32 {
+        Some(get(values, name, default))
+            .filter(|value| options.contains(value))
+            .unwrap_or(default)
+    }
+
+    let (settings, repeat, speed) = (
+        Settings::default(),
+        KeyRepeat::default(),
+        AnimationSpeed::default(),
+    );
+
+    (
+        Settings {
+            master_volume: get_option(
+                &values,
+                "master_volume",
+                &VOLUME_OPTIONS,
+                settings.master_volume,
+            ),
+            effects_volume: get_option(
+                &values,
+                "effects_volume",
+                &VOLUME_OPTIONS,
+                settings.effects_volume,
+            ),
+            bloom: get(&values, "bloom", settings.bloom),
+            bloom_intensity: get_option(
+                &values,
+                "bloom_intensity",
+                &BLOOM_INTENSITY_OPTIONS,
+                settings.bloom_intensity,
+            ),
+            fullscreen: get(&values, "fullscreen", settings.fullscreen),
+            window_width: get_option(
+                &values,
+                "window_width",
+                &WINDOW_WIDTH_OPTIONS,
+                settings.window_width,
+            ),
+            colourblind: get(&values, "colourblind", settings.colourblind),
+        },
+        KeyRepeat {
+            delay: get_option(&values, "repeat_delay", &REPEAT_DELAY_OPTIONS, repeat.delay),
+            interval: get_option(
+                &values,
+                "repeat_interval",
+                &REPEAT_INTERVAL_OPTIONS,
+                repeat.interval,
+            ),
+        },
+        AnimationSpeed(get_option(
+            &values,
+            "animation_speed",
+            &ANIMATION_SPEED_OPTIONS,
+            speed.0,
+        )),
+    )
+}
+
+fn save_settings(
+    backend: Res<SaveBackend>,
+    settings: Res<Settings>,
+    repeat: Res<KeyRepeat>,
+    speed: Res<AnimationSpeed>,
+) {
+    if !(settings.is_changed() || repeat.is_changed() || speed.is_changed()) || settings.is_added()
+    {
+        return;
+    }
+
+    let config = [
+        ("master_volume", settings.master_volume.to_string()),
+        ("effects_volume", settings.effects_volume.to_string()),
+        ("bloom", settings.bloom.to_string()),
+        ("bloom_intensity", settings.bloom_intensity.to_string()),
+        ("fullscreen", settings.fullscreen.to_string()),
+        ("window_width", settings.window_width.to_string()),
+        ("colourblind", settings.colourblind.to_string()),
+        ("repeat_delay", repeat.delay.to_string()),
+        ("repeat_interval", repeat.interval.to_string()),
+        ("animation_speed", speed.0.to_string()),
+    ]
+    .into_iter()
+    .map(|(name, value)| format!("{name}::{value}\n"))
+    .collect::<String>();
+
+    if let Err(err) = backend.0.write(SETTINGS_NAME, &config) {
+        log::warn!("couldn't save the settings: {err}");
+    }
+}
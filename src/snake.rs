@@ -1,15 +1,17 @@
 use bevy::{
     ecs::system::EntityCommands,
-    log,
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 use std::ops::{Div, Not, Sub};
 
 use crate::{
-    fade_out::FadeOutThisEnt,
-    input::{KeyBuffer, MoveEvent},
+    console::{Console, ConsoleApp, ConsoleArgs},
+    input::{MoveEvent, SwitchSnake},
+    keys::LockedDoorBumped,
     map::{GameMap, GridPos, GRID_CELL_SIZE},
+    settings::Settings,
+    turn::TurnEvent,
     GameState, GameplaySet,
 };
 
@@ -22,14 +24,37 @@ pub struct RewindCounter {
 #[derive(Debug, Component)]
 pub struct Move(pub Vec2);
 
-#[derive(Debug, Component)]
-pub struct TaggedDeath;
-
 #[derive(Debug, Component, Clone, Copy)]
 pub struct SnakeIndex(pub usize);
 
+// which snake a piece belongs to, numbered in reading order of the `%` starts
+#[derive(Debug, Component, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SnakeId(pub usize);
+
+#[derive(Debug, Resource, Default)]
+pub struct ActiveSnake(pub SnakeId);
+
+// triggered before the rewound pieces are removed, with their positions head first
+#[derive(Debug, Event)]
+pub struct SnakeRewound(pub Vec<[usize; 2]>);
+
+const SNAKE_COLORS: [Color; 4] = [
+    Color::srgb(50. / 255., 200. / 255., 50. / 255.),
+    Color::srgb(220. / 255., 160. / 255., 40. / 255.),
+    Color::srgb(50. / 255., 160. / 255., 230. / 255.),
+    Color::srgb(210. / 255., 70. / 255., 210. / 255.),
+];
+
+// picked to stay apart for the common kinds of colour blindness
+const COLOURBLIND_SNAKE_COLORS: [Color; 4] = [
+    Color::srgb(230. / 255., 159. / 255., 0. / 255.),
+    Color::srgb(86. / 255., 180. / 255., 233. / 255.),
+    Color::srgb(240. / 255., 228. / 255., 66. / 255.),
+    Color::srgb(213. / 255., 94. / 255., 0. / 255.),
+];
+
 #[derive(Debug, Component)]
-struct SnakeColor(Color);
+pub struct SnakeColor(pub Color);
 
 #[derive(Debug, Component, Clone, Copy)]
 pub struct SnakeSize(pub Vec2);
@@ -37,11 +62,86 @@ pub struct SnakeSize(pub Vec2);
 #[derive(Debug, Component)]
 pub struct CanMove;
 
+const MOVE_DURATION: f32 = 0.3;
+
+// how fast steps play out, 2 halves the time a step takes
+#[derive(Debug, Resource, Clone, Copy)]
+pub struct AnimationSpeed(pub f32);
+
+impl Default for AnimationSpeed {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+impl AnimationSpeed {
+    pub fn move_duration(self) -> f32 {
+        MOVE_DURATION / self.0
+    }
+}
+
+// heads walk through anything, toggled from the console
+#[derive(Debug, Resource, Default)]
+struct Noclip(bool);
+
+// a head that landed on ice and keeps going the same way
+#[derive(Debug, Component)]
+pub struct Sliding(pub Vec2);
+
 pub fn snake_plugin(app: &mut App) {
-    app.init_resource::<RewindCounter>().add_systems(
-        Update,
-        (on_move_snake, move_snake, cycle_snake, camera_follow).in_set(GameplaySet::Behavior),
-    );
+    app.init_resource::<RewindCounter>()
+        .init_resource::<ActiveSnake>()
+        .init_resource::<AnimationSpeed>()
+        .init_resource::<Noclip>()
+        .console_command(
+            "rewinds",
+            "rewinds <total> <individual>, sets the rewinds left",
+            rewinds_command,
+        )
+        .console_command("tp", "tp <x> <y>, moves the active head", tp_command)
+        .console_command("noclip", "toggles walking through walls", noclip_command)
+        .add_systems(
+            OnEnter(GameState::Gaming),
+            |mut active: ResMut<ActiveSnake>| active.0 = SnakeId(0),
+        )
+        .add_systems(
+            Update,
+            (
+                switch_snake.before(on_move_snake),
+                on_move_snake,
+                move_snake,
+                camera_follow,
+            )
+                .in_set(GameplaySet::Behavior),
+        )
+        .add_systems(Update, recolor_snakes.run_if(in_state(GameState::Gaming)));
+}
+
+pub fn snake_color(snake: SnakeId) -> Color {
+    SNAKE_COLORS[snake.0 % SNAKE_COLORS.len()]
+}
+
+// pieces spawn with the default palette, this swaps them over when the palette setting asks
+fn recolor_snakes(
+    settings: Res<Settings>,
+    mut pieces: Query<(Ref<SnakeId>, &mut SnakeColor, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let palette = if settings.colourblind {
+        COLOURBLIND_SNAKE_COLORS
+    } else {
+        SNAKE_COLORS
+    };
+
+    for (snake, mut color, material) in pieces
+        .iter_mut()
+        .filter(|(snake, ..)| settings.is_changed() || snake.is_added())
+    {
+        color.0 = palette[snake.0 % palette.len()];
+        if let Some(material) = materials.get_mut(material.id()) {
+            material.color = color.0;
+        }
+    }
 }
 
 pub fn spawn_snake_piece<'a>(
@@ -68,16 +168,20 @@ pub fn spawn_snake_piece<'a>(
 
 fn camera_follow(
     snake_pieces: Query<
-        (&SnakeIndex, &Transform),
+        (&SnakeIndex, &Transform, &SnakeId),
         (Or<(With<Move>, With<CanMove>)>, Without<Camera>),
     >,
     mut camera: Query<&mut Transform, (With<Camera>, Without<CanMove>)>,
+    active: Res<ActiveSnake>,
+    speed: Res<AnimationSpeed>,
     time: Res<Time>,
     mut start_time: Local<f32>,
     mut start_pos: Local<Vec2>,
 ) {
     let Some(head_pos) = snake_pieces
         .iter()
+        .filter(|piece| *piece.2 == active.0)
+        .map(|piece| (piece.0, piece.1))
         .fold(None::<(&SnakeIndex, &Transform)>, |max, piece| {
             Some(max.unwrap_or(piece)).map(|other| {
                 if other.0 .0 < piece.0 .0 {
@@ -96,7 +200,7 @@ fn camera_follow(
         .get_single_mut()
         .expect("only one camera should ever exists");
 
-    if *start_time + 0.3 < time.elapsed_seconds() {
+    if *start_time + speed.move_duration() < time.elapsed_seconds() {
         if camera_pos.translation.truncate() != head_pos {
             *start_time = time.elapsed_seconds();
             *start_pos = camera_pos.translation.truncate();
@@ -105,99 +209,39 @@ fn camera_follow(
     }
 
     camera_pos.translation = start_pos
-        .lerp(head_pos, (time.elapsed_seconds() - *start_time).div(0.3))
+        .lerp(
+            head_pos,
+            (time.elapsed_seconds() - *start_time).div(speed.move_duration()),
+        )
         .extend(0.)
 }
 
-fn cycle_snake(
-    mut cycle_buffer: ResMut<KeyBuffer>,
-    snake_pieces: Query<(Entity, &mut SnakeIndex, &SnakeColor, &GridPos)>,
-    mut map: ResMut<GameMap>,
-    mut commands: Commands,
-    mut rewinds: ResMut<RewindCounter>,
+fn switch_snake(
+    mut switch_event: EventReader<SwitchSnake>,
+    mut active: ResMut<ActiveSnake>,
+    heads: Query<(&SnakeId, Has<Move>, Has<Sliding>), Or<(With<CanMove>, With<Move>)>>,
 ) {
-    if !matches!(cycle_buffer.0.last(), Some(KeyCode::Enter)) {
-        return;
-    }
+    let switch_requested = switch_event.read().count() != 0;
 
-    if cycle_buffer.0.first_chunk() == Some(&[KeyCode::KeyU, KeyCode::KeyW, KeyCode::KeyU]) {
-        // uwu
-        log::info!("uwu");
-    }
+    let mut alive = heads.iter().map(|head| *head.0).collect::<Vec<_>>();
+    alive.sort();
+    alive.dedup();
 
-    const KEY_MAP: [KeyCode; 10] = [
-        KeyCode::Digit0,
-        KeyCode::Digit1,
-        KeyCode::Digit2,
-        KeyCode::Digit3,
-        KeyCode::Digit4,
-        KeyCode::Digit5,
-        KeyCode::Digit6,
-        KeyCode::Digit7,
-        KeyCode::Digit8,
-        KeyCode::Digit9,
-    ];
-
-    if cycle_buffer.0.len() > 10 {
-        cycle_buffer.0.clear();
+    if !switch_requested && alive.contains(&active.0) {
         return;
     }
 
-    let steps = if matches!(cycle_buffer.0.first(), Some(KeyCode::KeyC)) {
-        if let Some(steps) = cycle_buffer
-            .0
-            .get(1..cycle_buffer.0.len().saturating_sub(1))
-            .and_then(|slice| {
-                slice
-                    .iter()
-                    .rev()
-                    .map(|key| KEY_MAP.iter().position(|other_key| key == other_key))
-                    .enumerate()
-                    .fold(None::<usize>, |steps, (pos, step)| {
-                        if pos == 0 {
-                            step
-                        } else {
-                            Some(steps? + 10usize.pow(pos as u32).saturating_mul(step?))
-                        }
-                    })
-            })
-        {
-            steps
-        } else {
-            cycle_buffer.0.clear();
-            return;
-        }
-    } else {
-        cycle_buffer.0.clear();
+    if heads.iter().any(|(_, moving, sliding)| moving || sliding) {
         return;
-    };
-
-    let mut snake_ordered = snake_pieces.iter().collect::<Vec<_>>();
-    snake_ordered.sort_by(|other, piece| other.1 .0.cmp(&piece.1 .0));
-    snake_ordered.reverse();
-
-    (0..steps)
-        .filter_map(|i| snake_ordered.get(i))
-        .for_each(|piece| {
-            if let Some(map_tile) = map.get_mut(*piece.3) {
-                map_tile.top_removed();
-            }
-
-            // commands.entity(piece.0).despawn_recursive();
-            commands.entity(piece.0).insert(FadeOutThisEnt(piece.2 .0));
-        });
+    }
 
-    if let Some(mut new_head) = snake_ordered
-        .get(steps)
-        .map(|new_head| new_head.0)
-        .and_then(|new_head| commands.get_entity(new_head))
+    if let Some(next) = alive
+        .iter()
+        .find(|snake| **snake > active.0)
+        .or(alive.first())
     {
-        new_head.insert(CanMove);
+        active.0 = *next;
     }
-
-    cycle_buffer.0.clear();
-    rewinds.total -= 1;
-    rewinds.individual -= steps as isize;
 }
 
 fn on_move_snake(
@@ -211,43 +255,90 @@ fn on_move_snake(
         &mut GridPos,
         &SnakeSize,
         &SnakeColor,
+        &SnakeId,
         Option<&CanMove>,
+        Option<&Sliding>,
     )>,
     mut map: ResMut<GameMap>,
+    active: Res<ActiveSnake>,
+    noclip: Res<Noclip>,
 ) {
-    assert!(snake_pieces.iter().filter_map(|piece| piece.5).count() <= 1);
+    assert!(
+        snake_pieces
+            .iter()
+            .filter(|piece| piece.6.is_some() && *piece.5 == active.0)
+            .count()
+            <= 1
+    );
 
-    let Some(move_dir) = move_event.read().last().map(|move_event| move_event.0) else {
-        return;
-    };
+    let input_dir = move_event.read().last().map(|move_event| move_event.0);
 
-    for (ent, mut snake_index, mut grid_pos, size, color) in
+    for (ent, mut snake_index, mut grid_pos, size, color, snake, move_dir, sliding) in
         snake_pieces.iter_mut().filter_map(|piece| {
-            piece
-                .5
-                .map(|_| (piece.0, piece.1, piece.2, piece.3, piece.4))
+            // sliding heads keep going on their own, input is ignored until they stop
+            let move_dir = match piece.7 {
+                Some(sliding) if piece.6.is_some() => sliding.0,
+                Some(_) => return None,
+                None if piece.6.is_some() && *piece.5 == active.0 => input_dir?,
+                None => return None,
+            };
+
+            Some((
+                piece.0,
+                piece.1,
+                piece.2,
+                piece.3,
+                piece.4,
+                *piece.5,
+                move_dir,
+                piece.7.is_some(),
+            ))
         })
     {
         let grid_pos = &mut grid_pos.0;
 
-        if usize::try_from(grid_pos[0] as isize + move_dir.x as isize)
+        let next_pos = usize::try_from(grid_pos[0] as isize + move_dir.x as isize)
             .ok()
             .and_then(|x| {
                 Some([
                     x,
                     usize::try_from(grid_pos[1] as isize + move_dir.y as isize).ok()?,
                 ])
-            })
+            });
+
+        if let Some((pos, key)) = next_pos
+            .filter(|_| !noclip.0)
+            .and_then(|pos| Some((pos, map.get(pos)?.locked_with()?)))
+        {
+            commands.trigger(LockedDoorBumped(key, pos, snake));
+            if sliding {
+                commands.entity(ent).remove::<Sliding>();
+            }
+            continue;
+        }
+
+        if next_pos
             .and_then(|pos| map.get(pos))
-            .and_then(|tile| tile.is_occupied().not().then_some(()))
+            .and_then(|tile| (noclip.0 || tile.is_occupied().not()).then_some(()))
             .is_none()
         {
-            return;
+            if sliding {
+                commands.entity(ent).remove::<Sliding>();
+            }
+            continue;
         }
 
         map.get_mut(*grid_pos)
             .expect("previous checks should have valided this one too")
-            .snake_passes(*grid_pos, &mut commands);
+            .snake_passes(*grid_pos, snake, &mut commands);
+
+        // the head holds its tile too, so other snakes and beams can't go through it
+        if let Some(tile) = next_pos
+            .and_then(|pos| map.get_mut(pos))
+            .filter(|tile| !tile.is_occupied())
+        {
+            tile.hold_snake(snake);
+        }
 
         spawn_snake_piece(
             &mut commands,
@@ -257,28 +348,35 @@ fn on_move_snake(
             GridPos(*grid_pos),
             *size,
         )
-        .insert(*snake_index);
+        .insert((*snake_index, snake));
         snake_index.0 += 1;
         *grid_pos = [
             (grid_pos[0] as isize + move_dir.x as isize) as usize,
             (grid_pos[1] as isize + move_dir.y as isize) as usize,
         ];
 
+        // a slide keeps its `Sliding` while moving, the whole slide is one move and one turn
         commands
             .entity(ent)
             .remove::<CanMove>()
             .insert(Move(move_dir));
+
+        if !sliding {
+            commands.trigger(TurnEvent);
+        }
     }
 }
 
 fn move_snake(
     mut commands: Commands,
-    mut snake_pieces: Query<(Entity, &Move, &mut Transform)>,
+    mut snake_pieces: Query<(Entity, &Move, &mut Transform, &GridPos)>,
+    map: Res<GameMap>,
+    speed: Res<AnimationSpeed>,
     time: Res<Time>,
     mut dst: Local<Option<Vec2>>,
     mut elapsed: Local<f32>,
 ) {
-    let Some((ent, move_dir, mut transform)) = snake_pieces.get_single_mut().ok() else {
+    let Some((ent, move_dir, mut transform, grid_pos)) = snake_pieces.get_single_mut().ok() else {
         return;
     };
 
@@ -287,7 +385,7 @@ fn move_snake(
         *elapsed = time.elapsed().as_secs_f32();
     };
 
-    let dif = (time.elapsed().as_secs_f32() - *elapsed).div(0.3);
+    let dif = (time.elapsed().as_secs_f32() - *elapsed).div(speed.move_duration());
     let ddst = dst.unwrap();
 
     transform.translation = ddst
@@ -300,8 +398,79 @@ fn move_snake(
 
         if let Some(mut ent) = commands.get_entity(ent) {
             ent.remove::<Move>().try_insert(CanMove);
+
+            if map.get(*grid_pos).is_some_and(|tile| tile.is_slippery()) {
+                ent.try_insert(Sliding(move_dir.0));
+            } else {
+                ent.remove::<Sliding>();
+            }
         }
 
         dst.take();
     }
 }
+
+fn rewinds_command(
+    In(args): ConsoleArgs,
+    mut rewinds: ResMut<RewindCounter>,
+    mut console: ResMut<Console>,
+) {
+    let Some([total, individual]) = args
+        .iter()
+        .map(|arg| arg.parse::<isize>().ok())
+        .collect::<Option<Vec<_>>>()
+        .and_then(|args| <[isize; 2]>::try_from(args).ok())
+    else {
+        console.print("usage: rewinds <total> <individual>");
+        return;
+    };
+
+    rewinds.total = total;
+    rewinds.individual = individual;
+}
+
+// only the head moves, the body stays where it was
+fn tp_command(
+    In(args): ConsoleArgs,
+    mut heads: Query<(&mut GridPos, &mut Transform, &SnakeId), With<CanMove>>,
+    mut map: ResMut<GameMap>,
+    active: Res<ActiveSnake>,
+    mut console: ResMut<Console>,
+) {
+    let Some([x, y]) = args
+        .iter()
+        .map(|arg| arg.parse::<usize>().ok())
+        .collect::<Option<Vec<_>>>()
+        .and_then(|args| <[usize; 2]>::try_from(args).ok())
+    else {
+        console.print("usage: tp <x> <y>");
+        return;
+    };
+
+    if map.get([x, y]).is_none_or(|tile| tile.is_occupied()) {
+        console.print(format!("{x} {y} isn't free"));
+        return;
+    }
+
+    let Some((mut grid_pos, mut transform, _)) =
+        heads.iter_mut().find(|(_, _, snake)| **snake == active.0)
+    else {
+        console.print("no head to move");
+        return;
+    };
+
+    if let Some(tile) = map.get_mut(grid_pos.0) {
+        tile.snake_removed();
+    }
+    if let Some(tile) = map.get_mut([x, y]) {
+        tile.hold_snake(active.0);
+    }
+
+    grid_pos.0 = [x, y];
+    transform.translation = (GRID_CELL_SIZE * grid_pos.to_vec2()).extend(transform.translation.z);
+}
+
+fn noclip_command(_: ConsoleArgs, mut noclip: ResMut<Noclip>, mut console: ResMut<Console>) {
+    noclip.0 = !noclip.0;
+    console.print(format!("noclip {}", if noclip.0 { "on" } else { "off" }));
+}
@@ -1,6 +1,6 @@
 use bevy::{audio::Volume, prelude::*};
 
-use crate::snake::Move;
+use crate::{settings::Settings, snake::Move};
 
 #[derive(Debug, Default)]
 struct PitchLevels {
@@ -22,9 +22,10 @@ pub enum SoundEvent {
     PressurePlate,
     Select,
     Exit,
-    #[allow(dead_code)]
     Wind,
     Spike,
+    Pellet,
+    Star,
 }
 
 #[derive(Debug, Resource)]
@@ -66,15 +67,19 @@ fn on_sound(
     trigger: Trigger<SoundEvent>,
     mut commands: Commands,
     source: Res<AudioFiles>,
+    settings: Res<Settings>,
     mut pitches: Local<PitchLevels>,
 ) {
+    // the master volume is applied globally, this only scales the effects
+    let volume = |volume: f32| Volume::new(volume * settings.effects_volume);
+
     match trigger.event() {
         SoundEvent::SnakeMove => {
             _ = commands.spawn((AudioBundle {
                 source: source.snake_move.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: Volume::new(0.4),
+                    volume: volume(0.4),
                     speed: 1.3 + pitches.next(),
                     ..default()
                 },
@@ -85,6 +90,7 @@ fn on_sound(
                 source: source.pressure_plate.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
+                    volume: volume(1.),
                     ..default()
                 },
             })
@@ -94,7 +100,7 @@ fn on_sound(
                 source: source.comfirm.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: Volume::new(0.5),
+                    volume: volume(0.5),
                     speed: 2.0,
                     ..default()
                 },
@@ -105,6 +111,7 @@ fn on_sound(
                 source: source.exit.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
+                    volume: volume(1.),
                     ..default()
                 },
             })
@@ -114,6 +121,7 @@ fn on_sound(
                 source: source.wind.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
+                    volume: volume(1.),
                     ..default()
                 },
             })
@@ -123,11 +131,33 @@ fn on_sound(
                 source: source.spike.clone(),
                 settings: PlaybackSettings {
                     mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: Volume::new(0.6),
+                    volume: volume(0.6),
                     speed: 1.2,
                     ..default()
                 },
             })
         }
+        SoundEvent::Pellet => {
+            _ = commands.spawn(AudioBundle {
+                source: source.comfirm.clone(),
+                settings: PlaybackSettings {
+                    mode: bevy::audio::PlaybackMode::Despawn,
+                    volume: volume(0.5),
+                    speed: 3.0,
+                    ..default()
+                },
+            })
+        }
+        SoundEvent::Star => {
+            _ = commands.spawn(AudioBundle {
+                source: source.exit.clone(),
+                settings: PlaybackSettings {
+                    mode: bevy::audio::PlaybackMode::Despawn,
+                    volume: volume(0.5),
+                    speed: 2.0,
+                    ..default()
+                },
+            })
+        }
     }
 }
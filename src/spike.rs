@@ -1,6 +1,15 @@
 use bevy::prelude::*;
 
-use crate::{map::GridPos, snake::SnakeSize, sounds::SoundEvent, GameState};
+use crate::{
+    console::{Console, ConsoleApp, ConsoleArgs},
+    game_over::{DeathCause, PieceKilled},
+    map::GridPos,
+    pause::PauseState,
+    snake::SnakeSize,
+    sounds::SoundEvent,
+    turn::{is_turn_based, TurnEvent, TurnMode},
+    GameState,
+};
 #[derive(Debug, Resource)]
 pub struct SpikeSprites {
     spike: Handle<Image>,
@@ -34,13 +43,37 @@ pub struct Spike;
 #[derive(Debug, Resource)]
 struct SpikeTimer(Timer);
 
+// the atlas frame spikes are on in turn based mode, frame 2 is the deadly one
+#[derive(Debug, Resource, Default)]
+struct SpikeTurns(usize);
+
+// spikes keep moving when off, they just don't hurt
+#[derive(Debug, Resource)]
+struct SpikesDeadly(bool);
+
+const SPIKE_UP_FRAME: usize = 2;
+const SPIKE_FRAMES: usize = 4;
+
 pub fn spike_plugin(app: &mut App) {
     app.insert_resource(SpikeTimer(Timer::from_seconds(3.5, TimerMode::Repeating)))
         .init_resource::<SpikeSprites>()
+        .init_resource::<SpikeTurns>()
+        .insert_resource(SpikesDeadly(true))
+        .console_command(
+            "spikes",
+            "spikes on|off, whether spikes kill",
+            spikes_command,
+        )
+        .observe(on_turn_spike)
+        .add_systems(
+            OnEnter(GameState::Gaming),
+            |mut turns: ResMut<SpikeTurns>| turns.0 = 0,
+        )
         .add_systems(
             Update,
             activate_spike
-                .run_if(in_state(GameState::Gaming))
+                .run_if(in_state(PauseState::Running))
+                .run_if(not(is_turn_based))
                 .run_if(|spikes: Query<(), With<Spike>>| spikes.iter().next().is_some()),
         );
 }
@@ -69,6 +102,7 @@ fn activate_spike(
     mut commands: Commands,
     mut timer: ResMut<SpikeTimer>,
     time: Res<Time>,
+    deadly: Res<SpikesDeadly>,
 ) {
     timer.0.tick(time.delta());
 
@@ -93,12 +127,66 @@ fn activate_spike(
             commands.trigger(SoundEvent::Spike);
         }
 
+        if !deadly.0 {
+            return;
+        }
+
         for dead_pieces in spikes.iter().filter_map(|(_, spike_pos)| {
             snakes
                 .iter()
                 .find_map(|(ent, pos)| (*spike_pos == *pos).then_some(ent))
         }) {
-            commands.entity(dead_pieces).despawn_recursive();
+            commands.trigger_targets(PieceKilled(DeathCause::Spiked), dead_pieces);
         }
     }
 }
+
+fn on_turn_spike(
+    _: Trigger<TurnEvent>,
+    mut spikes: Query<(&mut TextureAtlas, &GridPos), With<Spike>>,
+    snakes: Query<(Entity, &GridPos), (With<SnakeSize>, Without<Spike>)>,
+    mut commands: Commands,
+    mut turns: ResMut<SpikeTurns>,
+    mode: Res<TurnMode>,
+    deadly: Res<SpikesDeadly>,
+) {
+    if *mode != TurnMode::TurnBased || spikes.is_empty() {
+        return;
+    }
+
+    turns.0 = (turns.0 + 1) % SPIKE_FRAMES;
+
+    for mut atlas in spikes.iter_mut().map(|(atlas, _)| atlas) {
+        atlas.index = turns.0;
+    }
+
+    if turns.0 != SPIKE_UP_FRAME {
+        return;
+    }
+
+    commands.trigger(SoundEvent::Spike);
+
+    if !deadly.0 {
+        return;
+    }
+
+    for dead_pieces in spikes.iter().filter_map(|(_, spike_pos)| {
+        snakes
+            .iter()
+            .find_map(|(ent, pos)| (*spike_pos == *pos).then_some(ent))
+    }) {
+        commands.trigger_targets(PieceKilled(DeathCause::Spiked), dead_pieces);
+    }
+}
+
+fn spikes_command(
+    In(args): ConsoleArgs,
+    mut deadly: ResMut<SpikesDeadly>,
+    mut console: ResMut<Console>,
+) {
+    match args.first().map(String::as_str) {
+        Some("on") => deadly.0 = true,
+        Some("off") => deadly.0 = false,
+        _ => console.print("usage: spikes on|off"),
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{map::MapMeta, GameState};
+
+#[derive(Debug, Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TurnMode {
+    #[default]
+    RealTime,
+    TurnBased,
+}
+
+// the global setting, maps can still force turn based with `turn_based::true`
+#[derive(Debug, Resource, Default)]
+pub struct PreferredTurnMode(pub TurnMode);
+
+// triggered once per accepted move or rewind
+#[derive(Debug, Event)]
+pub struct TurnEvent;
+
+pub fn turn_plugin(app: &mut App) {
+    app.init_resource::<TurnMode>()
+        .init_resource::<PreferredTurnMode>()
+        .add_systems(OnEnter(GameState::Gaming), init_turn_mode);
+}
+
+pub fn is_turn_based(mode: Res<TurnMode>) -> bool {
+    *mode == TurnMode::TurnBased
+}
+
+fn init_turn_mode(
+    mut mode: ResMut<TurnMode>,
+    preferred: Res<PreferredTurnMode>,
+    meta: Res<MapMeta>,
+) {
+    *mode = if meta.turn_based {
+        TurnMode::TurnBased
+    } else {
+        preferred.0
+    };
+}
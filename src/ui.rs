@@ -1,9 +1,17 @@
 use bevy::prelude::*;
 
-use crate::{input::KeyBuffer, snake::RewindCounter, title::UiResources, GameState};
+use crate::{
+    game_over::ExitLock,
+    keymap::{Action, Binding, Keymap},
+    keys::Keyring,
+    rewind::RewindMode,
+    snake::RewindCounter,
+    title::UiResources,
+    GameState,
+};
 
 #[derive(Debug, Component)]
-struct BufferText;
+struct RewindModeText;
 
 #[derive(Debug, Component)]
 struct CyclesText;
@@ -11,12 +19,24 @@ struct CyclesText;
 #[derive(Debug, Component)]
 struct RewindsText;
 
+#[derive(Debug, Component)]
+struct KeysText;
+
+#[derive(Debug, Component)]
+struct ExitText;
+
 pub fn game_ui_plugin(app: &mut App) {
     app.add_systems(OnEnter(GameState::Gaming), setup_gaming_ui)
         .add_systems(OnEnter(GameState::Loading), setup_loading_ui)
         .add_systems(
             Update,
-            (update_rewinds_count, update_buffer).run_if(in_state(GameState::Gaming)),
+            (
+                update_rewinds_count,
+                update_rewind_mode,
+                update_keys,
+                update_exit_lock,
+            )
+                .run_if(in_state(GameState::Gaming)),
         );
 }
 
@@ -66,7 +86,7 @@ fn setup_gaming_ui(mut commands: Commands, ui_resources: Res<UiResources>) {
             parent
                 .spawn(
                     TextBundle::from_sections(vec![
-                        TextSection::new("total rewinds left", text_style_red),
+                        TextSection::new("total rewinds left", text_style_red.clone()),
                         TextSection::new(" : ", text_style.clone()),
                         TextSection::new("2", text_style.clone()),
                     ])
@@ -77,11 +97,38 @@ fn setup_gaming_ui(mut commands: Commands, ui_resources: Res<UiResources>) {
                     }),
                 )
                 .insert(CyclesText);
+            parent
+                .spawn(
+                    TextBundle::from_sections(vec![
+                        TextSection::new("keys", text_style_red.clone()),
+                        TextSection::new(" : ", text_style.clone()),
+                        TextSection::new("", text_style.clone()),
+                    ])
+                    .with_style(Style {
+                        top: Val::Px(0.),
+                        left: Val::Px(10.),
+                        ..default()
+                    }),
+                )
+                .insert(KeysText);
+            parent
+                .spawn(
+                    TextBundle::from_sections(vec![
+                        TextSection::new("", text_style_red.clone()),
+                        TextSection::new("", text_style.clone()),
+                    ])
+                    .with_style(Style {
+                        top: Val::Px(0.),
+                        left: Val::Px(10.),
+                        ..default()
+                    }),
+                )
+                .insert(ExitText);
         });
     commands
         .spawn(
             TextBundle::from_sections(vec![
-                TextSection::new(":", text_style.clone()),
+                TextSection::new("", text_style_red),
                 TextSection::new("", text_style),
             ])
             .with_text_justify(JustifyText::Left)
@@ -93,7 +140,7 @@ fn setup_gaming_ui(mut commands: Commands, ui_resources: Res<UiResources>) {
             }),
         )
         .insert(StateScoped(GameState::Gaming))
-        .insert(BufferText);
+        .insert(RewindModeText);
 }
 
 fn setup_loading_ui(mut commands: Commands, ui_resources: Res<UiResources>) {
@@ -146,37 +193,66 @@ fn update_rewinds_count(
     }
 }
 
-fn update_buffer(
-    mut text: Query<&mut Text, With<BufferText>>,
-    key_buffer: Res<KeyBuffer>,
-    mut last_len: Local<usize>,
-) {
-    if *last_len == key_buffer.0.len() {
+fn update_keys(mut text: Query<&mut Text, With<KeysText>>, keyring: Res<Keyring>) {
+    if !keyring.is_changed() {
         return;
     }
-    *last_len = key_buffer.0.len();
 
     if let Some(mut text) = text.iter_mut().next() {
-        text.sections[1].value = key_buffer.0.iter().copied().map(map_key_buffer).collect();
+        text.sections[2].value = keyring
+            .0
+            .iter()
+            .filter(|held| held.opened.is_none())
+            .map(|held| held.key)
+            .collect();
     }
 }
 
-fn map_key_buffer(key: KeyCode) -> char {
-    match key {
-        KeyCode::Digit0 => '0',
-        KeyCode::Digit1 => '1',
-        KeyCode::Digit2 => '2',
-        KeyCode::Digit3 => '3',
-        KeyCode::Digit4 => '4',
-        KeyCode::Digit5 => '5',
-        KeyCode::Digit6 => '6',
-        KeyCode::Digit7 => '7',
-        KeyCode::Digit8 => '8',
-        KeyCode::Digit9 => '9',
-        KeyCode::KeyC => 'c',
-        KeyCode::KeyW => 'w',
-        KeyCode::KeyU => 'u',
-        KeyCode::Enter => ' ',
-        _ => ' ',
+fn update_exit_lock(mut text: Query<&mut Text, With<ExitText>>, lock: Res<ExitLock>) {
+    if !lock.is_changed() {
+        return;
+    }
+
+    if let Some(mut text) = text.iter_mut().next() {
+        let (label, reason) = match lock.0 {
+            Some(condition) => ("exit locked : ".to_string(), condition.describe()),
+            None => (String::new(), String::new()),
+        };
+        text.sections[0].value = label;
+        text.sections[1].value = reason;
+    }
+}
+
+fn update_rewind_mode(
+    mut text: Query<&mut Text, With<RewindModeText>>,
+    mode: Res<RewindMode>,
+    keymap: Res<Keymap>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+
+    let key = |action| {
+        keymap
+            .0
+            .get(&action)
+            .map(Binding::describe)
+            .unwrap_or_default()
+    };
+
+    if let Some(mut text) = text.iter_mut().next() {
+        let (label, help) = match mode.amount {
+            Some(amount) => (
+                format!("rewind {amount}"),
+                format!(
+                    " : {} to confirm, {} to cancel",
+                    key(Action::Confirm),
+                    key(Action::ClearRewind)
+                ),
+            ),
+            None => (String::new(), String::new()),
+        };
+        text.sections[0].value = label;
+        text.sections[1].value = help;
     }
 }